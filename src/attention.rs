@@ -0,0 +1,41 @@
+//! Escape sequences asking the terminal to point the user at the prompt.
+//!
+//! Terminals disagree on how to raise a notification, so the sequence is
+//! picked from what the environment says about the terminal: kitty's OSC 99,
+//! the OSC 777 of VTE-based terminals like GNOME Terminal, or the OSC 9 that
+//! iTerm2, WezTerm, foot and others understand.  A bell follows, which sets
+//! the urgency hint in most X11 terminals and marks the window in tmux and
+//! screen.  Under a multiplexer the notification is passed through to the
+//! terminal outside it, which tmux only does with `allow-passthrough` on.
+
+use std::env::var_os;
+
+/// The notification sequence for the terminal we think we're in.
+fn notification(message: &str) -> String {
+    let is_kitty = var_os("KITTY_WINDOW_ID").is_some()
+        || std::env::var("TERM").is_ok_and(|term| term == "xterm-kitty");
+    if is_kitty {
+        format!("\x1b]99;;{}\x1b\\", message)
+    } else if var_os("VTE_VERSION").is_some() {
+        format!("\x1b]777;notify;confirm;{}\x1b\\", message)
+    } else {
+        format!("\x1b]9;{}\x07", message)
+    }
+}
+
+/// Wrap `sequence` so a multiplexer hands it on instead of acting on it.
+fn pass_through(sequence: String) -> String {
+    if var_os("TMUX").is_some() {
+        format!("\x1bPtmux;{}\x1b\\", sequence.replace('\x1b', "\x1b\x1b"))
+    } else if var_os("STY").is_some() {
+        format!("\x1bP{}\x1b\\", sequence)
+    } else {
+        sequence
+    }
+}
+
+/// The sequences to write to the terminal to announce `message`.
+pub fn sequence(message: &str) -> String {
+    let message: String = message.chars().filter(|ch| !ch.is_control()).collect();
+    format!("{}\x07", pass_through(notification(&message)))
+}
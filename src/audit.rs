@@ -0,0 +1,296 @@
+//! A log of every answer, for reviewing what was approved on a machine.
+//!
+//! With `--audit-log` (or `audit-log = true` in a configuration file), each
+//! answer is appended to `$XDG_STATE_HOME/confirm/audit.jsonl`, or the file
+//! given, as a line of JSON with the time, the question's `--id`, the prompt,
+//! the answer, how long it took to answer and who answered: on Unix the
+//! account of the real user id, since `$USER` can be set to anything, with
+//! `$USER` kept as `env_user` when it names someone else.  Answers given
+//! without asking, like with `--yes`, are logged too, without a response
+//! time, and so are the answers to `confirm gate`.  `confirm history` reads
+//! the log back.
+
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use anyhow::{anyhow, bail, Context, Result};
+use clap::{Args, ValueEnum};
+use confirm_rs::text;
+use serde::{Deserialize, Serialize};
+
+use crate::history::state_dir;
+use crate::MainOptions;
+
+const SECS_PER_DAY: u64 = 24 * 60 * 60;
+
+#[derive(Debug, Serialize, Deserialize)]
+struct Entry {
+    /// Seconds since 1970.
+    time: u64,
+    id: Option<String>,
+    prompt: String,
+    answer: String,
+    response_ms: Option<u64>,
+    user: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    uid: Option<u32>,
+    /// `$USER` when it differs from `user`, only for reference.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    env_user: Option<String>,
+}
+
+/// The log file `--audit-log` names, or the default one.
+pub fn path(given: Option<&Path>) -> Result<PathBuf> {
+    match given {
+        Some(path) => Ok(path.to_path_buf()),
+        None => state_dir()
+            .map(|dir| dir.join("audit.jsonl"))
+            .ok_or_else(|| anyhow!("Cannot find the audit log without HOME or XDG_STATE_HOME")),
+    }
+}
+
+/// The name the user database gives `uid`.
+#[cfg(unix)]
+fn account_name(uid: libc::uid_t) -> Option<String> {
+    // SAFETY: an all-zero passwd is a valid value to be filled in.
+    let mut entry: libc::passwd = unsafe { std::mem::zeroed() };
+    let mut buf = vec![0 as libc::c_char; 16 * 1024];
+    let mut found = std::ptr::null_mut();
+    // SAFETY: the pointers are valid for the call, and `buf.len()` is the
+    // size of the buffer the strings in `entry` are written to.
+    let failed =
+        unsafe { libc::getpwuid_r(uid, &mut entry, buf.as_mut_ptr(), buf.len(), &mut found) };
+    if failed != 0 || found.is_null() {
+        return None;
+    }
+    // SAFETY: on success `pw_name` points to a C string in `buf`.
+    let name = unsafe { std::ffi::CStr::from_ptr(entry.pw_name) };
+    Some(name.to_string_lossy().into_owned())
+}
+
+/// Who is answering, their uid, and `$USER` if it claims someone else.
+fn user() -> (String, Option<u32>, Option<String>) {
+    let named = std::env::var("USER").or_else(|_| std::env::var("USERNAME"));
+    let named = named.ok().filter(|name| !name.is_empty());
+    #[cfg(unix)]
+    {
+        // SAFETY: getuid has no preconditions and always succeeds.
+        let uid = unsafe { libc::getuid() };
+        let user = account_name(uid).unwrap_or_else(|| format!("uid {}", uid));
+        let claimed = named.filter(|name| *name != user);
+        (user, Some(uid), claimed)
+    }
+    #[cfg(not(unix))]
+    (named.unwrap_or_default(), None, None)
+}
+
+fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |since| since.as_secs())
+}
+
+/// Append the answer to the log, `response` being how long it took unless
+/// it was given without asking.
+pub fn record(opts: &MainOptions, answer: &str, response: Option<Duration>) -> Result<()> {
+    let log = match &opts.audit_log {
+        Some(log) => path(log.as_deref())?,
+        None => return Ok(()),
+    };
+    let (user, uid, env_user) = user();
+    let entry = Entry {
+        time: now(),
+        id: opts.id.clone(),
+        prompt: opts.prompt.clone(),
+        answer: answer.to_string(),
+        response_ms: response.map(|response| response.as_millis() as u64),
+        user,
+        uid,
+        env_user,
+    };
+    if let Some(dir) = log.parent().filter(|dir| !dir.as_os_str().is_empty()) {
+        fs::create_dir_all(dir).with_context(|| format!("Cannot create {}", dir.display()))?;
+    }
+    let line = format!("{}\n", serde_json::to_string(&entry)?);
+    OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(&log)
+        .and_then(|mut file| file.write_all(line.as_bytes()))
+        .with_context(|| format!("Cannot write the audit log {}", log.display()))
+}
+
+/// Days since 1970 for a date, after Howard Hinnant's `days_from_civil`.
+fn days_from_civil(year: i64, month: i64, day: i64) -> i64 {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let year_of_era = year - era * 400;
+    let day_of_year = (153 * ((month + 9) % 12) + 2) / 5 + day - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    era * 146_097 + day_of_era - 719_468
+}
+
+/// The date for days since 1970, the inverse of [`days_from_civil`].
+fn civil_from_days(days: i64) -> (i64, i64, i64) {
+    let days = days + 719_468;
+    let era = days.div_euclid(146_097);
+    let day_of_era = days - era * 146_097;
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let shifted_month = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * shifted_month + 2) / 5 + 1;
+    let month = if shifted_month < 10 {
+        shifted_month + 3
+    } else {
+        shifted_month - 9
+    };
+    let year = year_of_era + era * 400 + i64::from(month <= 2);
+    (year, month, day)
+}
+
+/// Seconds since 1970 at the start of a `YYYY-MM-DD` day, in UTC.
+fn parse_date(text: &str) -> Result<u64, String> {
+    let invalid = || format!("expected a date like 2024-12-31, not {:?}", text);
+    let parts: Vec<i64> = text
+        .split('-')
+        .map(|part| part.parse().map_err(|_| invalid()))
+        .collect::<Result<_, _>>()?;
+    let [year, month, day] = parts[..] else {
+        return Err(invalid());
+    };
+    if !(1..=12).contains(&month) || !(1..=31).contains(&day) {
+        return Err(invalid());
+    }
+    u64::try_from(days_from_civil(year, month, day))
+        .map(|days| days * SECS_PER_DAY)
+        .map_err(|_| invalid())
+}
+
+fn format_time(time: u64) -> String {
+    let (year, month, day) = civil_from_days((time / SECS_PER_DAY) as i64);
+    let secs = time % SECS_PER_DAY;
+    format!(
+        "{:04}-{:02}-{:02} {:02}:{:02}:{:02}",
+        year,
+        month,
+        day,
+        secs / 3600,
+        secs / 60 % 60,
+        secs % 60
+    )
+}
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq, ValueEnum)]
+enum Outcome {
+    Yes,
+    No,
+    Neutral,
+}
+
+#[derive(Clone, Debug, Args)]
+pub struct HistoryOptions {
+    /// Only show answers to the question with this --id
+    #[arg(long)]
+    id: Option<String>,
+
+    /// Only show this answer
+    #[arg(long, value_enum)]
+    answer: Option<Outcome>,
+
+    /// Only show answers from this day on, like 2024-12-31 (UTC)
+    #[arg(long, value_name = "DATE", value_parser = parse_date)]
+    since: Option<u64>,
+
+    /// Only show answers up to and including this day (UTC)
+    #[arg(long, value_name = "DATE", value_parser = parse_date)]
+    until: Option<u64>,
+
+    /// The log to read, instead of the one --audit-log writes to
+    #[arg(long, value_name = "PATH")]
+    file: Option<PathBuf>,
+}
+
+impl HistoryOptions {
+    fn matches(&self, entry: &Entry) -> bool {
+        self.id
+            .as_ref()
+            .is_none_or(|id| entry.id.as_ref() == Some(id))
+            && self.answer.is_none_or(|answer| {
+                answer
+                    .to_possible_value()
+                    .is_some_and(|value| value.get_name() == entry.answer)
+            })
+            && self.since.is_none_or(|since| entry.time >= since)
+            && self
+                .until
+                .is_none_or(|until| entry.time < until + SECS_PER_DAY)
+    }
+}
+
+pub fn run(opts: &MainOptions, history: &HistoryOptions) -> Result<()> {
+    let configured = opts.audit_log.clone().flatten();
+    let log = path(history.file.as_deref().or(configured.as_deref()))?;
+    let text = match fs::read_to_string(&log) {
+        Ok(text) => text,
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => {
+            bail!("No audit log at {}, see --audit-log", log.display())
+        }
+        Err(err) => return Err(anyhow!("Cannot read {}: {}", log.display(), err)),
+    };
+
+    let mut counts = [("yes", 0), ("no", 0), ("neutral", 0)];
+    let (mut shown, mut timed, mut total_ms) = (0, 0, 0);
+    for (number, line) in text.lines().enumerate() {
+        if line.trim().is_empty() {
+            continue;
+        }
+        let entry: Entry = serde_json::from_str(line).with_context(|| {
+            format!("Invalid entry on line {} of {}", number + 1, log.display())
+        })?;
+        if !history.matches(&entry) {
+            continue;
+        }
+        shown += 1;
+        if let Some((_, count)) = counts.iter_mut().find(|(name, _)| *name == entry.answer) {
+            *count += 1;
+        }
+        let response = match entry.response_ms {
+            Some(ms) => {
+                timed += 1;
+                total_ms += ms;
+                format!(" ({:.1}s)", ms as f64 / 1000.0)
+            }
+            None => String::new(),
+        };
+        println!(
+            "{}  {:<7}  {:<8}  {:<8}  {}{}",
+            format_time(entry.time),
+            entry.answer,
+            entry.user,
+            entry.id.as_deref().unwrap_or("-"),
+            text::sanitize(&entry.prompt),
+            response
+        );
+    }
+
+    let counts: Vec<String> = counts
+        .iter()
+        .filter(|(_, count)| *count > 0)
+        .map(|(name, count)| format!("{} {}", count, name))
+        .collect();
+    match shown {
+        0 => println!("No matching answers"),
+        1 => println!("1 answer: {}", counts.join(", ")),
+        _ => println!("{} answers: {}", shown, counts.join(", ")),
+    }
+    if timed > 0 {
+        println!(
+            "Average response time: {:.1}s",
+            total_ms as f64 / timed as f64 / 1000.0
+        );
+    }
+    Ok(())
+}
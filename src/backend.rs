@@ -0,0 +1,150 @@
+//! Where a question is shown and its answers come from.
+
+use std::io::{self, BufRead, Write};
+
+use crate::events::Event;
+
+/// The input and output a [`Question`](crate::Question) is asked on.
+pub trait Backend {
+    /// Display text, typically the prompt, without adding a newline.
+    fn show(&mut self, text: &str) -> io::Result<()>;
+
+    /// Read a line of input after `prompt` has been shown, returning `None`
+    /// once the input has been closed.  Failing with
+    /// [`TimedOut`](io::ErrorKind::TimedOut) takes the default answer, and
+    /// with [`Interrupted`](io::ErrorKind::Interrupted) gives up on the
+    /// question, see [`Decision`](crate::Decision).
+    fn read_line(&mut self, prompt: &str) -> io::Result<Option<String>>;
+
+    /// Read a single key without waiting for a newline, returning `None` once
+    /// the input has been closed.  Keys shouldn't be echoed.  An
+    /// [`Unsupported`](io::ErrorKind::Unsupported) error switches the
+    /// question to reading lines instead, other errors are taken as for
+    /// [`read_line`](Backend::read_line).
+    fn read_key(&mut self, prompt: &str) -> io::Result<Option<char>>;
+
+    /// Whether text that has been shown can be rewritten with terminal
+    /// escape sequences.
+    fn can_redraw(&self) -> bool {
+        false
+    }
+
+    /// The width of the display in columns, if known, for working out where
+    /// long prompts wrap.
+    fn columns(&self) -> Option<usize> {
+        None
+    }
+
+    /// Report progress, see [`Event`].  Ignored unless overridden.
+    fn event(&mut self, _event: Event) {}
+
+    /// Display a diagnostic, like the reason for giving up.
+    fn message(&mut self, text: &str) {
+        eprintln!("{}", text);
+    }
+}
+
+/// A backend over plain streams, like WASI's stdin and stdout or the pipes
+/// of a web-based terminal.  Single keys are read from the input as they
+/// arrive, so the other end decides whether to send them unbuffered.
+///
+/// Prompts go to `output`, and diagnostics to stderr unless
+/// [`with_messages`](Streams::with_messages) gives them somewhere else.
+/// Either can be a [`Callback`] to route the text into a UI of its own.
+#[derive(Debug)]
+pub struct Streams<R, W, M = io::Stderr> {
+    input: R,
+    output: W,
+    messages: M,
+}
+
+impl<R: BufRead, W: Write> Streams<R, W> {
+    pub fn new(input: R, output: W) -> Self {
+        Self {
+            input,
+            output,
+            messages: io::stderr(),
+        }
+    }
+}
+
+impl<R: BufRead, W: Write, M: Write> Streams<R, W, M> {
+    /// Write diagnostics, like the reason for giving up, to `messages`, one
+    /// per line.
+    pub fn with_messages<N: Write>(self, messages: N) -> Streams<R, W, N> {
+        Streams {
+            input: self.input,
+            output: self.output,
+            messages,
+        }
+    }
+
+    pub fn into_inner(self) -> (R, W) {
+        (self.input, self.output)
+    }
+}
+
+impl<R: BufRead, W: Write, M: Write> Backend for Streams<R, W, M> {
+    fn show(&mut self, text: &str) -> io::Result<()> {
+        self.output.write_all(text.as_bytes())?;
+        self.output.flush()
+    }
+
+    fn message(&mut self, text: &str) {
+        // Nowhere left to report a failure to report.
+        let _ = writeln!(self.messages, "{}", text).and_then(|()| self.messages.flush());
+    }
+
+    fn read_line(&mut self, _prompt: &str) -> io::Result<Option<String>> {
+        let mut line = String::new();
+        Ok(match self.input.read_line(&mut line)? {
+            0 => None,
+            _ => Some(line),
+        })
+    }
+
+    fn read_key(&mut self, _prompt: &str) -> io::Result<Option<char>> {
+        let mut bytes = Vec::with_capacity(4);
+        loop {
+            let mut byte = [0];
+            if self.input.read(&mut byte)? == 0 {
+                return Ok(None);
+            }
+            bytes.push(byte[0]);
+            match std::str::from_utf8(&bytes) {
+                Ok(text) => return Ok(text.chars().next()),
+                Err(err) if err.error_len().is_some() || bytes.len() == 4 => {
+                    return Ok(Some(char::REPLACEMENT_CHARACTER))
+                }
+                Err(_) => {}
+            }
+        }
+    }
+}
+
+/// A writer handing what is written to a function as text, for prompts
+/// shown in something other than a stream, like a status bar or a log
+/// window.  Bytes that aren't valid UTF-8 are replaced.
+///
+/// ```
+/// use confirm_rs::{Callback, Decision, Question, Streams};
+///
+/// let mut shown = String::new();
+/// let output = Callback(|text: &str| shown.push_str(text));
+/// let decision = Question::new("Continue?").ask(&mut Streams::new(&b"y\n"[..], output));
+/// assert_eq!(decision, Decision::Yes);
+/// assert_eq!(shown, "Continue? [y/n]: ");
+/// ```
+#[derive(Debug)]
+pub struct Callback<F>(pub F);
+
+impl<F: FnMut(&str)> Write for Callback<F> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        (self.0)(&String::from_utf8_lossy(buf));
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
@@ -0,0 +1,156 @@
+//! Picking one of a list of options, like the choose steps of a wizard.
+//!
+//! The options come from the command line, or from stdin with `-`, as in
+//! `ls | confirm choose - | xargs rm`.  stdin is the options then, so the
+//! question is asked on `/dev/tty`.  The chosen option is printed on stdout.
+
+use std::io::{stdin, stdout, IsTerminal, Read, Write};
+#[cfg(unix)]
+use std::path::PathBuf;
+
+use anyhow::{bail, Context, Result};
+use clap::{Args, ValueEnum};
+
+use crate::term::PromptSink;
+use crate::wizard::Asker;
+use crate::MainOptions;
+
+#[derive(Clone, Debug, Args)]
+pub struct ChooseOptions {
+    /// The options to choose from, or - to read them from stdin, one per
+    /// line
+    #[arg(required = true)]
+    options: Vec<String>,
+
+    /// The question shown above the options
+    #[arg(long, default_value = "Choose one:")]
+    prompt: String,
+
+    /// The option picked on an empty answer
+    #[arg(long, value_name = "OPTION")]
+    default: Option<String>,
+
+    /// Options read from stdin are separated by NUL characters instead of
+    /// newlines, as from `find -print0`, and the choice is printed followed
+    /// by a NUL
+    #[arg(short = '0', long)]
+    null: bool,
+
+    /// What to print for the option picked
+    #[arg(long, value_enum, default_value_t = Print::Value)]
+    print: Print,
+
+    /// Count the options from 1 instead of 0 when printing the index
+    #[arg(long)]
+    one_based: bool,
+
+    /// What goes between the index and the option with --print both, a tab
+    /// unless given
+    #[arg(
+        long,
+        value_name = "TEXT",
+        default_value = "\t",
+        hide_default_value = true
+    )]
+    separator: String,
+}
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq, ValueEnum)]
+enum Print {
+    /// The option itself
+    Value,
+    /// Its position in the list
+    Index,
+    /// The position and the option
+    Both,
+}
+
+/// Read the options from stdin, skipping empty ones.
+fn read_options(separator: u8) -> Result<Vec<String>> {
+    if stdin().is_terminal() {
+        bail!("Nothing to choose from: pipe the options into confirm choose -");
+    }
+    let mut input = Vec::new();
+    stdin()
+        .lock()
+        .read_to_end(&mut input)
+        .context("Cannot read the options from stdin")?;
+    Ok(input
+        .split(|&byte| byte == separator)
+        .map(|option| {
+            String::from_utf8_lossy(option)
+                .trim_end_matches('\r')
+                .to_string()
+        })
+        .filter(|option| !option.is_empty())
+        .collect())
+}
+
+/// Ask on the terminal rather than stdin, which is the options.
+#[cfg(unix)]
+fn ask_on_terminal(opts: &mut MainOptions) -> Result<()> {
+    if opts.tty.is_none() {
+        opts.tty = Some(PathBuf::from("/dev/tty"));
+    }
+    Ok(())
+}
+
+#[cfg(not(unix))]
+fn ask_on_terminal(_: &mut MainOptions) -> Result<()> {
+    bail!("Reading the options from stdin isn't supported on this platform")
+}
+
+pub fn run(opts: &MainOptions, choose: &ChooseOptions) -> Result<()> {
+    if opts.machine || opts.shell_output {
+        bail!("--machine and --shell-output can't be used, stdout is the choice");
+    }
+    let separator = match choose.null {
+        true => b'\0',
+        false => b'\n',
+    };
+    let mut opts = opts.clone();
+    let options = match choose.options.as_slice() {
+        [dash] if dash == "-" => {
+            ask_on_terminal(&mut opts)?;
+            read_options(separator)?
+        }
+        options => options.to_vec(),
+    };
+    if options.is_empty() {
+        bail!("Nothing to choose from");
+    }
+    if let Some(default) = &choose.default {
+        if !options.contains(default) {
+            bail!("The default {:?} isn't one of the options", default);
+        }
+    }
+
+    let picked = match (opts.preset(), &choose.default) {
+        (None, _) => {
+            // stdout is reserved for the choice.
+            let mut terminal = opts.terminal()?;
+            if let PromptSink::Stdout = terminal.sink {
+                terminal.sink = PromptSink::Stderr;
+            }
+            terminal.events = None;
+            let mut asker = Asker {
+                opts: &opts,
+                terminal,
+            };
+            asker.pick(&choose.prompt, &options, choose.default.as_deref(), None)?
+        }
+        (Some(_), Some(default)) => options.iter().position(|opt| opt == default).unwrap_or(0),
+        (Some(_), None) => bail!("Not asking, and there's no --default to choose"),
+    };
+    let index = picked + usize::from(choose.one_based);
+    let printed = match choose.print {
+        Print::Value => options[picked].clone(),
+        Print::Index => index.to_string(),
+        Print::Both => format!("{}{}{}", index, choose.separator, options[picked]),
+    };
+    let mut out = stdout().lock();
+    out.write_all(printed.as_bytes())?;
+    out.write_all(&[separator])?;
+    out.flush()?;
+    Ok(())
+}
@@ -0,0 +1,434 @@
+//! Defaults for the command line options from configuration files.
+//!
+//! Two files are read, both TOML with keys named after the long options:
+//! the user's `$XDG_CONFIG_HOME/confirm/config.toml` (or
+//! `~/.config/confirm/config.toml`), and the first `.confirm.toml` found
+//! searching upward from the working directory, which lets a repository
+//! pin the policy for everyone running its scripts.
+//!
+//! Options given on the command line take precedence over the user's file,
+//! which takes precedence over the project's file, which takes precedence
+//! over the built-in defaults.
+//!
+//! Either file can define named bundles of options as `[profile.<name>]`
+//! tables, applied with `--profile <name>` on top of the rest of the
+//! configuration.
+//!
+//! Keys naming files to write, like `audit-log`, are only read from the
+//! user's file: a project file comes with whatever was checked out, and
+//! mustn't get confirm to append to paths of its choosing.  Nor may it make
+//! questions answer themselves, so `timeout` and `grace` are the user's too,
+//! and a project's `default` can't be "yes".
+//!
+//! With `--json-request`, a JSON object on stdin with the same keys and the
+//! prompt is applied on top of the files, still below the command line.
+
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+use anyhow::{anyhow, bail, Context, Result};
+use clap::parser::ValueSource;
+use clap::{ArgMatches, CommandFactory, FromArgMatches, ValueEnum};
+use serde::de::Error as _;
+use serde::{Deserialize, Deserializer};
+
+use crate::{
+    diagnostics, environment, precheck, Answer, Highlight, Icons, Lang, MainOptions, TimeoutMode,
+};
+
+/// The name of the project file.
+const PROJECT_FILE: &str = ".confirm.toml";
+
+/// Keys only the user's file may set, see the module docs.
+const USER_ONLY: &[&str] = &["audit-log", "timeout", "grace"];
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename_all = "kebab-case", deny_unknown_fields)]
+struct Config {
+    full_words: Option<bool>,
+    boolean_aliases: Option<bool>,
+    #[serde(default, deserialize_with = "value_enum")]
+    default: Option<Answer>,
+    no_enter: Option<bool>,
+    ask_count: Option<u8>,
+    tries_format: Option<String>,
+    quiet: Option<bool>,
+    neutral: Option<String>,
+    neutral_code: Option<u8>,
+    danger: Option<bool>,
+    require_tty: Option<bool>,
+    accessible: Option<bool>,
+    ascii: Option<bool>,
+    #[serde(default, deserialize_with = "parsed")]
+    lang: Option<Lang>,
+    #[serde(default, deserialize_with = "value_enum")]
+    highlight: Option<Highlight>,
+    #[serde(default, deserialize_with = "icons")]
+    icons: Option<Option<Icons>>,
+    #[cfg_attr(not(feature = "sound"), allow(dead_code))]
+    sound: Option<bool>,
+    attention: Option<bool>,
+    #[cfg_attr(not(unix), allow(dead_code))]
+    renotify: Option<u32>,
+    #[cfg_attr(not(unix), allow(dead_code))]
+    timeout: Option<u32>,
+    #[cfg_attr(not(unix), allow(dead_code))]
+    #[serde(default, deserialize_with = "value_enum")]
+    timeout_mode: Option<TimeoutMode>,
+    #[cfg_attr(not(unix), allow(dead_code))]
+    grace: Option<u32>,
+    #[serde(default, deserialize_with = "audit_log")]
+    audit_log: Option<Option<Option<PathBuf>>>,
+    /// Named option bundles, only checked here and applied as tables.
+    #[serde(default)]
+    profile: BTreeMap<String, toml::Table>,
+}
+
+/// Read a value spelled as on the command line.
+fn value_enum<'de, D, T>(deserializer: D) -> Result<Option<T>, D::Error>
+where
+    D: Deserializer<'de>,
+    T: ValueEnum,
+{
+    let value = String::deserialize(deserializer)?;
+    T::from_str(&value, true)
+        .map(Some)
+        .map_err(|_| D::Error::custom(format!("unknown value {:?}", value)))
+}
+
+fn parsed<'de, D, T>(deserializer: D) -> Result<Option<T>, D::Error>
+where
+    D: Deserializer<'de>,
+    T: FromStr,
+    T::Err: std::fmt::Display,
+{
+    let value = String::deserialize(deserializer)?;
+    value.parse().map(Some).map_err(D::Error::custom)
+}
+
+/// `icons = true` for the default symbols, or the symbols themselves.
+fn icons<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Option<Option<Icons>>, D::Error> {
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum Setting {
+        Enabled(bool),
+        Symbols(String),
+    }
+    match Setting::deserialize(deserializer)? {
+        Setting::Enabled(enabled) => Ok(Some(enabled.then(Icons::default))),
+        Setting::Symbols(symbols) => symbols
+            .parse()
+            .map(|icons| Some(Some(icons)))
+            .map_err(D::Error::custom),
+    }
+}
+
+/// `audit-log = true` for the default file, or the file itself.
+fn audit_log<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> Result<Option<Option<Option<PathBuf>>>, D::Error> {
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum Setting {
+        Enabled(bool),
+        Path(PathBuf),
+    }
+    Ok(Some(match Setting::deserialize(deserializer)? {
+        Setting::Enabled(enabled) => enabled.then_some(None),
+        Setting::Path(path) => Some(Some(path)),
+    }))
+}
+
+/// Use the configured value unless the option was given on the command line.
+fn set<T>(matches: &ArgMatches, id: &str, option: &mut T, value: Option<T>) {
+    if let Some(value) = value {
+        if matches.value_source(id) != Some(ValueSource::CommandLine) {
+            *option = value;
+        }
+    }
+}
+
+fn user_file() -> Option<PathBuf> {
+    let config = match std::env::var_os("XDG_CONFIG_HOME") {
+        Some(dir) if !dir.is_empty() => PathBuf::from(dir),
+        _ => PathBuf::from(std::env::var_os("HOME")?).join(".config"),
+    };
+    Some(config.join("confirm").join("config.toml"))
+}
+
+fn project_file() -> Option<PathBuf> {
+    let cwd = std::env::current_dir().ok()?;
+    let found = cwd
+        .ancestors()
+        .map(|dir| dir.join(PROJECT_FILE))
+        .find(|path| path.is_file());
+    if found.is_none() {
+        log::debug!("No {} in {} or its parents", PROJECT_FILE, cwd.display());
+    }
+    found
+}
+
+/// The config files that exist, in the order they are loaded.
+pub fn files() -> Vec<PathBuf> {
+    [project_file(), user_file()]
+        .into_iter()
+        .flatten()
+        .filter(|path| path.is_file())
+        .collect()
+}
+
+/// Read a file as a table, with nothing to read if it doesn't exist.
+fn read_table(path: &Path) -> Result<toml::Table> {
+    match fs::read_to_string(path) {
+        Ok(text) => {
+            log::debug!("Loading config {}", path.display());
+            toml::from_str(&text).with_context(|| format!("Cannot parse config {}", path.display()))
+        }
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => {
+            log::debug!("No config at {}", path.display());
+            Ok(toml::Table::new())
+        }
+        Err(err) => Err(anyhow!("Cannot read config {}: {}", path.display(), err)),
+    }
+}
+
+/// Overlay `top` onto `base`, merging tables key by key.
+fn merge(base: &mut toml::Table, top: toml::Table) {
+    for (key, value) in top {
+        match (base.get_mut(&key), value) {
+            (Some(toml::Value::Table(base)), toml::Value::Table(top)) => merge(base, top),
+            (_, value) => {
+                base.insert(key, value);
+            }
+        }
+    }
+}
+
+impl Config {
+    /// Check a file's contents, profiles included.
+    fn check(table: &toml::Table) -> Result<(), String> {
+        let config = Self::deserialize(table.clone()).map_err(|err| err.to_string())?;
+        for (name, profile) in config.profile {
+            Self::deserialize(profile).map_err(|err| format!("in profile {:?}: {}", name, err))?;
+        }
+        Ok(())
+    }
+
+    /// A setting in `table` or its profiles that only the user's file may
+    /// make.
+    fn user_only_key(table: &toml::Table) -> Option<&'static str> {
+        let profiles = match table.get("profile") {
+            Some(toml::Value::Table(profiles)) => profiles.values().collect(),
+            _ => Vec::new(),
+        };
+        let mut tables = std::iter::once(table).chain(
+            profiles
+                .into_iter()
+                .filter_map(|profile| profile.as_table()),
+        );
+        let default_yes = |table: &toml::Table| {
+            let default = table.get("default").and_then(toml::Value::as_str);
+            default.is_some_and(|value| {
+                <Answer as ValueEnum>::from_str(value, true) == Ok(Answer::Yes)
+            })
+        };
+        tables.find_map(|table| match default_yes(table) {
+            true => Some("default = \"yes\""),
+            false => {
+                (table.keys()).find_map(|key| USER_ONLY.iter().copied().find(|only| only == key))
+            }
+        })
+    }
+
+    fn load(profile: Option<&str>) -> Result<Self> {
+        let mut table = toml::Table::new();
+        let files = [(project_file(), false), (user_file(), true)];
+        for (path, is_user) in files {
+            let Some(path) = path else {
+                continue;
+            };
+            let top = read_table(&path)?;
+            // Checked on its own first, so errors point at the right file.
+            Self::check(&top)
+                .map_err(|err| anyhow!("Invalid config {}: {}", path.display(), err))?;
+            if let Some(key) = Self::user_only_key(&top).filter(|_| !is_user) {
+                bail!(
+                    "Invalid config {}: {} can only be set in the user's config file",
+                    path.display(),
+                    key
+                );
+            }
+            merge(&mut table, top);
+        }
+        if let Some(name) = profile {
+            log::debug!("Applying profile {:?}", name);
+            let overlay = match table.get("profile").and_then(|profiles| profiles.get(name)) {
+                Some(toml::Value::Table(overlay)) => overlay.clone(),
+                _ => bail!("No profile named {:?} in the config files", name),
+            };
+            merge(&mut table, overlay);
+        }
+        Self::deserialize(table).map_err(|err| anyhow!("Invalid config: {}", err))
+    }
+
+    /// Fill in the options that weren't given on the command line.
+    ///
+    /// `--danger` already implies full words, a single try and "no" as the
+    /// default, and `--check` sets the default, so configured values of
+    /// those are left out rather than conflicting.  A configured `danger`
+    /// gives way in turn to options on the command line it would conflict
+    /// with.  Other conflicts are caught by `MainOptions::check`.
+    fn apply(self, opts: &mut MainOptions, matches: &ArgMatches) {
+        let given = |id: &str| matches.value_source(id) == Some(ValueSource::CommandLine);
+        let weakening = [
+            "default",
+            "ask_count",
+            "full_words",
+            "no_enter",
+            "boolean_aliases",
+            "check",
+            "validator",
+            #[cfg(feature = "script")]
+            "script",
+        ];
+        let danger = self.danger.filter(|_| !weakening.into_iter().any(given));
+        set(matches, "danger", &mut opts.danger, danger);
+        let (danger, check) = (opts.danger, opts.check.is_some());
+        set(
+            matches,
+            "full_words",
+            &mut opts.full_words,
+            self.full_words.filter(|_| !danger),
+        );
+        set(
+            matches,
+            "boolean_aliases",
+            &mut opts.boolean_aliases,
+            self.boolean_aliases,
+        );
+        set(
+            matches,
+            "default",
+            &mut opts.default,
+            self.default.filter(|_| !(danger || check)),
+        );
+        set(matches, "no_enter", &mut opts.no_enter, self.no_enter);
+        set(
+            matches,
+            "ask_count",
+            &mut opts.ask_count,
+            self.ask_count.filter(|_| !danger),
+        );
+        set(
+            matches,
+            "tries_format",
+            &mut opts.tries_format,
+            self.tries_format.map(Some),
+        );
+        set(matches, "quiet", &mut opts.quiet, self.quiet);
+        set(
+            matches,
+            "neutral",
+            &mut opts.neutral,
+            self.neutral.map(Some),
+        );
+        set(
+            matches,
+            "neutral_code",
+            &mut opts.neutral_code,
+            self.neutral_code,
+        );
+        set(
+            matches,
+            "require_tty",
+            &mut opts.require_tty,
+            self.require_tty,
+        );
+        set(matches, "accessible", &mut opts.accessible, self.accessible);
+        set(matches, "ascii", &mut opts.ascii, self.ascii);
+        set(matches, "lang", &mut opts.lang, self.lang.map(Some));
+        set(matches, "highlight", &mut opts.highlight, self.highlight);
+        set(matches, "icons", &mut opts.icons, self.icons);
+        #[cfg(feature = "sound")]
+        set(matches, "sound", &mut opts.sound, self.sound);
+        set(matches, "attention", &mut opts.attention, self.attention);
+        #[cfg(unix)]
+        set(
+            matches,
+            "renotify",
+            &mut opts.renotify,
+            self.renotify.map(Some),
+        );
+        #[cfg(unix)]
+        set(
+            matches,
+            "timeout",
+            &mut opts.timeout,
+            self.timeout.map(Some),
+        );
+        #[cfg(unix)]
+        set(
+            matches,
+            "timeout_mode",
+            &mut opts.timeout_mode,
+            self.timeout_mode,
+        );
+        #[cfg(unix)]
+        set(matches, "grace", &mut opts.grace, self.grace.map(Some));
+        set(matches, "audit_log", &mut opts.audit_log, self.audit_log);
+    }
+}
+
+/// Take the question from a JSON request on stdin, and ask it on the
+/// terminal since stdin is used up.
+#[cfg(unix)]
+fn read_request(opts: &mut MainOptions, matches: &ArgMatches) -> Result<()> {
+    use serde_json::Value;
+
+    let mut request: serde_json::Map<String, Value> =
+        serde_json::from_reader(std::io::stdin().lock())
+            .context("Cannot read the JSON request on stdin")?;
+    let prompt = match request.remove("prompt") {
+        Some(Value::String(prompt)) => Some(prompt),
+        Some(_) => bail!("Invalid request: the prompt must be a string"),
+        None => None,
+    };
+    let config = Config::deserialize(Value::Object(request))
+        .map_err(|err| anyhow!("Invalid request: {}", err))?;
+    if !config.profile.is_empty() {
+        bail!("Invalid request: profiles can only be defined in config files");
+    }
+    if config.audit_log.is_some() {
+        bail!("Invalid request: audit-log can only be set in the user's config file");
+    }
+    log::debug!("Read the question from a JSON request");
+    set(matches, "prompt", &mut opts.prompt, prompt);
+    config.apply(opts, matches);
+    if opts.tty.is_none() {
+        opts.tty = Some(PathBuf::from("/dev/tty"));
+    }
+    Ok(())
+}
+
+/// Parse the command line, with defaults from the configuration files
+/// unless `--no-config` is given.
+pub fn parse_options() -> Result<MainOptions> {
+    let matches = MainOptions::command().get_matches();
+    let mut opts = MainOptions::from_arg_matches(&matches).unwrap_or_else(|err| err.exit());
+    diagnostics::init(opts.verbose);
+    match opts.no_config {
+        true => log::debug!("Ignoring the config files"),
+        false => Config::load(opts.profile.as_deref())?.apply(&mut opts, &matches),
+    }
+    #[cfg(unix)]
+    if opts.json_request {
+        read_request(&mut opts, &matches)?;
+    }
+    precheck::apply(&mut opts)?;
+    #[cfg(feature = "script")]
+    crate::script::apply(&mut opts)?;
+    environment::apply(&mut opts, &matches)?;
+    Ok(opts)
+}
@@ -0,0 +1,41 @@
+//! Desktop notification backend.
+//!
+//! Raises the question as a notification with Yes/No actions through the
+//! freedesktop notification service on D-Bus, for scripts started from a
+//! graphical session where no terminal is attached.
+
+use anyhow::{Context, Result};
+use notify_rust::{Notification, Timeout};
+
+use crate::Answer;
+
+/// Ask the question as a notification and wait for the user to act on it.
+///
+/// Clicking the notification body picks the default answer, and dismissing it
+/// counts as a negative response.  A neutral answer gets its own action.
+pub fn ask(prompt: &str, default: Answer, neutral: Option<&str>) -> Result<Answer> {
+    let mut notification = Notification::new();
+    notification
+        .appname("confirm")
+        .summary(prompt)
+        .action("yes", "Yes")
+        .action("no", "No")
+        .timeout(Timeout::Never);
+    if let Some(neutral) = neutral {
+        notification.action("neutral", neutral);
+    }
+    let handle = notification
+        .show()
+        .context("Cannot show desktop notification")?;
+
+    let mut answer = Answer::No;
+    handle.wait_for_action(|action| {
+        answer = match action {
+            "yes" => Answer::Yes,
+            "neutral" => Answer::Neutral,
+            "default" if default == Answer::Yes => Answer::Yes,
+            _ => Answer::No,
+        };
+    });
+    Ok(answer)
+}
@@ -0,0 +1,61 @@
+//! Verbose diagnostics on stderr, for `-v` and `-vv`, and the pieces the
+//! doctor and self-test reports share, so the two describe things the same
+//! way.
+
+use log::{LevelFilter, Log, Metadata, Record};
+
+struct Stderr;
+
+impl Log for Stderr {
+    fn enabled(&self, metadata: &Metadata) -> bool {
+        metadata.level() <= log::max_level()
+    }
+
+    fn log(&self, record: &Record) {
+        if self.enabled(record.metadata()) {
+            let level = record.level().as_str().to_ascii_lowercase();
+            eprintln!("confirm: {}: {}", level, record.args());
+        }
+    }
+
+    fn flush(&self) {}
+}
+
+/// The first line of a report: the version and the platform.
+pub fn header() {
+    println!(
+        "confirm {} on {} ({})",
+        env!("CARGO_PKG_VERSION"),
+        std::env::consts::OS,
+        std::env::consts::ARCH
+    );
+}
+
+/// Describe an environment variable's value for a report.
+pub fn var(name: &str) -> String {
+    match std::env::var(name) {
+        Ok(value) => format!("{}={}", name, value),
+        Err(_) => format!("{} unset", name),
+    }
+}
+
+/// Describe whether something is a terminal for a report.
+pub fn terminal(is: bool) -> &'static str {
+    match is {
+        true => "a terminal",
+        false => "not a terminal",
+    }
+}
+
+/// Show diagnostics up to the level picked by how many times `-v` was given:
+/// debug for one, and trace, which includes the raw input, for more.
+pub fn init(verbosity: u8) {
+    let level = match verbosity {
+        0 => return,
+        1 => LevelFilter::Debug,
+        _ => LevelFilter::Trace,
+    };
+    // Only fails if a logger is already set, which can't happen here.
+    let _ = log::set_logger(&Stderr);
+    log::set_max_level(level);
+}
@@ -0,0 +1,190 @@
+//! A report on how confirm sees the environment it runs in.
+//!
+//! Unlike the self-test, nothing is started or asked: the report describes
+//! the terminal, color, locale and configuration as found, and which ways of
+//! showing the prompt and reading the answer the options given before the
+//! subcommand would pick, for working out why confirm behaves differently in
+//! one place than in another.
+
+use std::io::IsTerminal;
+
+use anyhow::Result;
+use confirm_rs::{dumb_terminal, locale_is_ascii_only, no_color};
+
+use crate::diagnostics::{header, terminal, var};
+use crate::{config, MainOptions};
+#[cfg(unix)]
+use crate::{term, PromptSink};
+
+fn row(label: &str, value: impl std::fmt::Display) {
+    println!("{:<12} {}", label, value);
+}
+
+/// Whether color is used, and why not if it isn't.
+fn color() -> String {
+    let set = |var| std::env::var(var).is_ok_and(|value| !value.is_empty());
+    if !no_color() {
+        return "on".to_string();
+    }
+    let reason = if dumb_terminal() {
+        "the terminal is dumb".to_string()
+    } else if set("NO_COLOR") {
+        var("NO_COLOR")
+    } else {
+        var("CLICOLOR")
+    };
+    format!("off, {}", reason)
+}
+
+/// Where answers would be read from, and whether single keys can be read
+/// from there.
+#[cfg(unix)]
+fn answer_source(opts: &MainOptions) -> (String, String) {
+    use std::os::fd::AsRawFd;
+
+    if let Some(fd) = opts.answer_fd {
+        let is = term::is_terminal(fd);
+        return (
+            format!("--answer-fd {}, {}", fd, terminal(is)),
+            raw_mode(fd),
+        );
+    }
+    if let Some(path) = &opts.tty {
+        return match term::open_tty(path) {
+            Ok(tty) => {
+                let is = term::is_terminal(tty.as_raw_fd());
+                let raw = raw_mode(tty.as_raw_fd());
+                (format!("{}, {}", path.display(), terminal(is)), raw)
+            }
+            Err(err) => (format!("{:#}", err), "unavailable".to_string()),
+        };
+    }
+    let is = std::io::stdin().is_terminal();
+    (
+        format!("stdin, {}", terminal(is)),
+        raw_mode(std::io::stdin().as_raw_fd()),
+    )
+}
+
+/// Whether single keys can be read from `fd`.
+#[cfg(unix)]
+fn raw_mode(fd: std::os::fd::RawFd) -> String {
+    match term::RawMode::enable(fd) {
+        // Dropping it restores the terminal right away.
+        Ok(Some(_raw)) if dumb_terminal() => "available, but not used on a dumb terminal".into(),
+        Ok(Some(_raw)) => "available".to_string(),
+        Ok(None) => "unavailable, not a terminal".to_string(),
+        Err(err) => format!("unavailable, {}", err),
+    }
+}
+
+/// How the answer would be read.
+fn reading(opts: &MainOptions) -> &'static str {
+    match opts.no_enter {
+        true if dumb_terminal() => "whole lines, since the terminal is dumb",
+        true => "single keys",
+        false if opts.stdin_answer => "the first line of stdin, asked once",
+        false => "whole lines",
+    }
+}
+
+pub fn run(opts: &MainOptions) -> Result<()> {
+    header();
+    row("stdin", terminal(std::io::stdin().is_terminal()));
+    row("stdout", terminal(std::io::stdout().is_terminal()));
+    row("stderr", terminal(std::io::stderr().is_terminal()));
+    let dumb = match dumb_terminal() {
+        true => ", dumb",
+        false => "",
+    };
+    row("terminal", format!("{}{}", var("TERM"), dumb));
+    row("color", color());
+
+    let render = opts.render();
+    let charset = match locale_is_ascii_only() {
+        true => "ASCII only",
+        false => "UTF-8",
+    };
+    row(
+        "locale",
+        format!(
+            "{}, {}, {}: {}, {}",
+            var("LC_ALL"),
+            var("LC_CTYPE"),
+            var("LANG"),
+            render.lang.code(),
+            charset
+        ),
+    );
+
+    match opts.no_config {
+        true => row("config", "ignored, --no-config"),
+        false => {
+            let files = config::files();
+            if files.is_empty() {
+                row("config", "none found");
+            }
+            for path in files {
+                row("config", path.display());
+            }
+        }
+    }
+    if let Some(profile) = &opts.profile {
+        row("profile", profile);
+    }
+
+    if opts.always_yes || opts.always_no {
+        let answer = match opts.always_yes {
+            true => "yes",
+            false => "no",
+        };
+        // The environment and the guards have had their say by now.
+        row(
+            "answer",
+            format!("{} without asking, see -v for why", answer),
+        );
+    }
+    #[cfg(all(feature = "desktop", unix, not(target_os = "macos")))]
+    if opts.desktop {
+        row("backend", "desktop notification");
+        return Ok(());
+    }
+    #[cfg(unix)]
+    {
+        let sink = match &opts.tty {
+            Some(path) => term::open_tty(path).map(PromptSink::Tty),
+            None => Ok(opts.default_sink()),
+        };
+        match sink {
+            Err(err) => row("prompt", format!("{:#}", err)),
+            Ok(sink) => {
+                let size = match (sink.columns(), sink.rows()) {
+                    (Some(columns), Some(rows)) => format!(", {}x{}", columns, rows),
+                    _ => String::new(),
+                };
+                row(
+                    "prompt",
+                    format!("on {}, {}{}", sink, terminal(sink.is_terminal()), size),
+                );
+            }
+        }
+        let (source, raw) = answer_source(opts);
+        row("answers", source);
+        row("raw mode", raw);
+    }
+    #[cfg(not(unix))]
+    {
+        let sink = opts.default_sink();
+        row(
+            "prompt",
+            format!("on {}, {}", sink, terminal(sink.is_terminal())),
+        );
+        row(
+            "answers",
+            format!("stdin, {}", terminal(std::io::stdin().is_terminal())),
+        );
+        row("raw mode", "not used on this platform");
+    }
+    row("reading", reading(opts));
+    Ok(())
+}
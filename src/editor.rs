@@ -0,0 +1,89 @@
+//! A small line editor for answers typed on a terminal.
+//!
+//! Editing happens at the end of the line only; the up and down arrows step
+//! through previous answers, replacing the line.
+
+use std::io::{self, Write};
+use std::os::fd::AsRawFd;
+
+use confirm_rs::text;
+
+use crate::term::{AnswerSource, Key, PromptSink, RawMode};
+
+/// Replace `shown` after the prompt with `line`, going back up over the
+/// rows it wrapped onto.
+fn redraw(sink: &mut PromptSink, prompt: &str, shown: &str, line: &str) -> io::Result<()> {
+    let width = text::display_width(prompt) + text::display_width(shown);
+    let up = sink
+        .columns()
+        .map_or(0, |columns| text::rows(width, columns) - 1);
+    if up > 0 {
+        write!(sink, "\x1b[{}A", up)?;
+    }
+    write!(sink, "\r\x1b[J{}{}", prompt, line)
+}
+
+/// Read a line, returning `None` at end of input.
+pub fn read_line(
+    source: &mut AnswerSource,
+    sink: &mut PromptSink,
+    prompt: &str,
+    history: &[String],
+) -> io::Result<Option<String>> {
+    let raw = RawMode::enable_without_signals(source.as_raw_fd())?;
+    let mut line = String::new();
+    // Position in the history while recalling, and the line being typed
+    // before recall started.
+    let mut recalled = history.len();
+    let mut draft = String::new();
+
+    loop {
+        match source.read_key_event()? {
+            Key::Char(ch) if !ch.is_control() => {
+                line.push(ch);
+                write!(sink, "{}", ch)?;
+            }
+            Key::Backspace if !line.is_empty() => {
+                // Wide characters and wrapped lines can't be erased with a
+                // single backspace.
+                let shown = line.clone();
+                line.pop();
+                redraw(sink, prompt, &shown, &line)?;
+            }
+            Key::Up if recalled > 0 => {
+                if recalled == history.len() {
+                    draft = line.clone();
+                }
+                recalled -= 1;
+                let shown = std::mem::replace(&mut line, history[recalled].clone());
+                redraw(sink, prompt, &shown, &line)?;
+            }
+            Key::Down if recalled < history.len() => {
+                recalled += 1;
+                let entry = match history.get(recalled) {
+                    Some(entry) => entry.clone(),
+                    None => draft.clone(),
+                };
+                let shown = std::mem::replace(&mut line, entry);
+                redraw(sink, prompt, &shown, &line)?;
+            }
+            Key::Enter => {
+                writeln!(sink)?;
+                return Ok(Some(line));
+            }
+            Key::Eof if line.is_empty() => {
+                writeln!(sink)?;
+                return Ok(None);
+            }
+            Key::Interrupt => {
+                writeln!(sink)?;
+                drop(raw);
+                // SAFETY: raising a signal on ourselves has no preconditions.
+                unsafe { libc::raise(libc::SIGINT) };
+                return Err(io::ErrorKind::Interrupted.into());
+            }
+            _ => {}
+        }
+        sink.flush()?;
+    }
+}
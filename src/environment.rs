@@ -0,0 +1,109 @@
+//! Conventions other tools use to run unattended, so confirm behaves
+//! predictably inside existing provisioning tooling.
+//!
+//! Whatever answers here answers like `--yes` or `--no`, so it covers `gate`,
+//! `filter`, `choose` and the wizard as well as the plain prompt.
+//!
+//! `DEBIAN_FRONTEND=noninteractive` and `NONINTERACTIVE=1` answer every
+//! question with its default, or "no" without one, and `ASSUME_YES=1` answers
+//! "yes".  Neither overrides `--yes` or `--no` on the command line, or
+//! `--if` and `--unless` deciding not to ask, and `ASSUME_YES` is ignored for
+//! `--danger` questions.  The color conventions are handled by
+//! [`confirm_rs::no_color`].
+//!
+//! Answers can also be seeded ahead of time: `CONFIRM_ANSWER_<ID>` answers
+//! the question given that `--id`, and `CONFIRM_ANSWER` any question, with
+//! "yes" or "no".  These come before the conventions above, since they name
+//! the answer rather than guess it, but a "yes" in the generic variable is
+//! ignored for `--danger` questions like `ASSUME_YES`.  They're the names
+//! `--shell-output` prints answers in, so values it listed in
+//! `CONFIRM_REPORTED` are skipped: an answer evaluated and exported in a
+//! script doesn't answer the questions after it.
+
+use anyhow::{bail, Result};
+use clap::parser::ValueSource;
+use clap::ArgMatches;
+
+use crate::{shell, Answer, MainOptions};
+
+/// Whether `var` is set to something other than an empty or false value.
+fn enabled(var: &str) -> bool {
+    std::env::var(var).is_ok_and(|value| {
+        !matches!(
+            value.to_ascii_lowercase().as_str(),
+            "" | "0" | "false" | "no" | "off"
+        )
+    })
+}
+
+fn noninteractive() -> Option<&'static str> {
+    if std::env::var("DEBIAN_FRONTEND").is_ok_and(|value| value == "noninteractive") {
+        return Some("DEBIAN_FRONTEND=noninteractive");
+    }
+    enabled("NONINTERACTIVE").then_some("NONINTERACTIVE")
+}
+
+/// The answer seeded for this question, with the variable it came from.
+fn seeded(opts: &MainOptions) -> Result<Option<(String, Answer)>> {
+    let specific = opts.id.as_deref().map(shell::answer_var);
+    let vars = specific.into_iter().chain([shell::ANSWER_VAR.to_string()]);
+    for var in vars {
+        let Ok(value) = std::env::var(&var) else {
+            continue;
+        };
+        if shell::reported(&var, &value) {
+            log::debug!("{} was printed by an earlier confirm, not using it", var);
+            continue;
+        }
+        let answer = match value.to_ascii_lowercase().as_str() {
+            "" => continue,
+            "y" | "yes" => Answer::Yes,
+            "n" | "no" => Answer::No,
+            _ => bail!("{} is {:?}, expected yes or no", var, value),
+        };
+        return Ok(Some((var, answer)));
+    }
+    Ok(None)
+}
+
+/// Answer without asking if the environment gives the answer, or says
+/// nobody is there to ask.
+pub fn apply(opts: &mut MainOptions, matches: &ArgMatches) -> Result<()> {
+    let given = |id| matches.value_source(id) == Some(ValueSource::CommandLine);
+    if given("always_yes") || given("always_no") || opts.always_yes {
+        return Ok(());
+    }
+    if let Some((var, answer)) = seeded(opts)? {
+        match answer {
+            Answer::Yes if opts.danger && var == shell::ANSWER_VAR => {
+                log::debug!("Ignoring {} for a --danger question", var);
+            }
+            Answer::Yes => {
+                log::debug!("{} is set, answering yes", var);
+                opts.always_yes = true;
+                return Ok(());
+            }
+            _ => {
+                log::debug!("{} is set, answering no", var);
+                opts.always_no = true;
+                return Ok(());
+            }
+        }
+    }
+    if let Some(var) = noninteractive() {
+        log::debug!("{} is set, answering with the default", var);
+        match opts.default {
+            Answer::Yes if !opts.danger => opts.always_yes = true,
+            _ => opts.always_no = true,
+        }
+    } else if enabled("ASSUME_YES") {
+        match opts.danger {
+            true => log::debug!("Ignoring ASSUME_YES for a --danger question"),
+            false => {
+                log::debug!("ASSUME_YES is set, answering yes");
+                opts.always_yes = true;
+            }
+        }
+    }
+    Ok(())
+}
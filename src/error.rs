@@ -0,0 +1,15 @@
+//! Errors from the library's fallible operations.
+
+use thiserror::Error;
+
+/// Why a setting couldn't be parsed.
+#[derive(Debug, Clone, Eq, PartialEq, Error)]
+#[non_exhaustive]
+pub enum ConfirmError {
+    /// A language code or locale name without a catalog.
+    #[error("no translation for {0:?}, expected one of en, de, fr, es")]
+    UnknownLanguage(String),
+    /// Icons not given as three symbols separated by commas.
+    #[error("expected three symbols separated by commas, like ?,+,-")]
+    InvalidIcons(String),
+}
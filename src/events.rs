@@ -0,0 +1,67 @@
+//! Machine-readable event stream for `--machine`.
+//!
+//! Each event is written to stdout as a single line of JSON, so programs
+//! wrapping confirm can follow along and mirror its state in their own UI.
+//! Writing them takes the `serde` feature; without it a [`Backend`] still
+//! gets them as values.
+//!
+//! [`Backend`]: crate::Backend
+
+#[cfg(feature = "serde")]
+use std::io::{stdout, Stdout, Write};
+
+#[derive(Debug)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize),
+    serde(tag = "event", rename_all = "kebab-case")
+)]
+pub enum Event<'a> {
+    /// The prompt was displayed and confirm is waiting for an answer.
+    PromptShown { prompt: &'a str, attempt: u32 },
+    /// The answer wasn't one of the accepted ones.
+    InvalidInput { input: &'a str },
+    /// The question is about to be asked again.
+    Retry {
+        attempt: u32,
+        remaining: Option<u32>,
+    },
+    /// A final answer was given.
+    Answered { answer: &'static str },
+    /// No answer came in time, so the default `answer` was taken.
+    TimedOut { answer: &'static str },
+    /// No answer could be obtained, see `reason`.
+    Aborted { reason: &'static str },
+}
+
+/// Writes events as JSON lines, to stdout unless given another writer.
+#[cfg(feature = "serde")]
+#[derive(Debug)]
+pub struct EventStream<W = Stdout> {
+    out: W,
+}
+
+#[cfg(feature = "serde")]
+impl Default for EventStream {
+    fn default() -> Self {
+        Self::new(stdout())
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<W: Write> EventStream<W> {
+    pub fn new(out: W) -> Self {
+        Self { out }
+    }
+
+    pub fn emit(&mut self, event: Event) {
+        let out = &mut self.out;
+        let written = serde_json::to_writer(&mut *out, &event)
+            .map_err(std::io::Error::from)
+            .and_then(|()| writeln!(out))
+            .and_then(|()| out.flush());
+        if let Err(err) = written {
+            eprintln!("Cannot write event: {}", err);
+        }
+    }
+}
@@ -0,0 +1,116 @@
+//! Answering from another shell through a named pipe, for `--fifo`.
+//!
+//! The pipe exists while confirm is waiting for an answer, and a line
+//! written to it, like `echo yes > /tmp/deploy.answer`, is taken as the
+//! answer just as if it had been typed.  It's opened for writing as well as
+//! reading, so it never reads as closed between writers.
+//!
+//! A confirm killed by a signal leaves its pipe behind, so a pipe of the
+//! user's found at the path is taken to be such a leftover and replaced.
+//! Anything else there is refused.
+
+use std::ffi::CString;
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, Read};
+use std::os::fd::{AsRawFd, RawFd};
+use std::os::unix::ffi::OsStrExt;
+use std::os::unix::fs::OpenOptionsExt;
+use std::path::{Path, PathBuf};
+
+use anyhow::{anyhow, Context, Result};
+
+#[derive(Debug)]
+pub struct Fifo {
+    path: PathBuf,
+    file: File,
+    /// What was read past the last complete line.
+    pending: Vec<u8>,
+}
+
+impl Fifo {
+    /// Make a pipe at `path` that only the user can write to, replacing one
+    /// left there by an earlier run.
+    pub fn create(path: &Path) -> Result<Self> {
+        let name = CString::new(path.as_os_str().as_bytes())
+            .map_err(|_| anyhow!("Invalid FIFO path {}", path.display()))?;
+        // SAFETY: `name` is a valid C string for the duration of the call.
+        let mut made = unsafe { libc::mkfifo(name.as_ptr(), 0o600) } == 0;
+        if !made
+            && io::Error::last_os_error().kind() == io::ErrorKind::AlreadyExists
+            && leftover(path)
+        {
+            log::debug!("Replacing the FIFO {} left behind", path.display());
+            fs::remove_file(path)
+                .with_context(|| format!("Cannot remove the old FIFO {}", path.display()))?;
+            // SAFETY: as above.
+            made = unsafe { libc::mkfifo(name.as_ptr(), 0o600) } == 0;
+        }
+        if !made {
+            return Err(io::Error::last_os_error())
+                .with_context(|| format!("Cannot create the FIFO {}", path.display()));
+        }
+        let opened = OpenOptions::new()
+            .read(true)
+            .write(true)
+            .custom_flags(libc::O_NONBLOCK)
+            .open(path);
+        match opened {
+            Ok(file) => Ok(Self {
+                path: path.to_path_buf(),
+                file,
+                pending: Vec::new(),
+            }),
+            Err(err) => {
+                let _ = fs::remove_file(path);
+                Err(err).with_context(|| format!("Cannot open the FIFO {}", path.display()))
+            }
+        }
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// The first complete line written to the pipe and not yet taken, if
+    /// any, without waiting for one.
+    pub fn read_line(&mut self) -> io::Result<Option<String>> {
+        let mut chunk = [0; 256];
+        loop {
+            match self.file.read(&mut chunk) {
+                Ok(0) => break,
+                Ok(read) => self.pending.extend_from_slice(&chunk[..read]),
+                Err(err) if err.kind() == io::ErrorKind::WouldBlock => break,
+                Err(err) if err.kind() == io::ErrorKind::Interrupted => {}
+                Err(err) => return Err(err),
+            }
+        }
+        let Some(end) = self.pending.iter().position(|&byte| byte == b'\n') else {
+            return Ok(None);
+        };
+        let line: Vec<u8> = self.pending.drain(..=end).collect();
+        Ok(Some(String::from_utf8_lossy(&line).into_owned()))
+    }
+}
+
+/// Whether `path`, without following a symlink, is a pipe of the user's.
+fn leftover(path: &Path) -> bool {
+    use std::os::unix::fs::{FileTypeExt, MetadataExt};
+
+    // SAFETY: getuid has no preconditions and always succeeds.
+    let uid = unsafe { libc::getuid() };
+    fs::symlink_metadata(path).is_ok_and(|meta| meta.file_type().is_fifo() && meta.uid() == uid)
+}
+
+impl AsRawFd for Fifo {
+    fn as_raw_fd(&self) -> RawFd {
+        self.file.as_raw_fd()
+    }
+}
+
+impl Drop for Fifo {
+    fn drop(&mut self) {
+        if let Err(err) = fs::remove_file(&self.path) {
+            log::debug!("Cannot remove the FIFO {}: {}", self.path.display(), err);
+        }
+    }
+}
@@ -0,0 +1,147 @@
+//! Filtering items by asking about each of them, like `rm -i` for any
+//! pipeline.
+//!
+//! `find . -name '*.orig' | confirm filter "Delete {}?" | xargs rm` asks
+//! about every line on the terminal and passes on only the approved ones.
+//! Besides yes and no, "a" approves the item and all the rest without asking,
+//! and "q" drops it and all the rest.
+
+use std::io::{stdin, stdout, BufRead, IsTerminal, Write};
+use std::path::PathBuf;
+
+use anyhow::{bail, Result};
+use clap::Args;
+use confirm_rs::i18n::fill;
+use confirm_rs::text;
+
+use crate::wizard::Asker;
+use crate::{Answer, MainOptions};
+
+#[derive(Clone, Debug, Args)]
+pub struct FilterOptions {
+    /// The question for each item, with {} standing for the item
+    #[arg(default_value = "Keep {}?")]
+    prompt: String,
+
+    /// Items are separated by NUL characters instead of newlines, as from
+    /// `find -print0`, and are printed the same way
+    #[arg(short = '0', long)]
+    null: bool,
+}
+
+enum Reply {
+    Yes,
+    No,
+    /// Yes to this and every following item.
+    All,
+    /// No to this and every following item.
+    Quit,
+}
+
+/// The item as shown in the prompt, with control characters like newlines
+/// in file names escaped and bidirectional overrides removed.
+fn shown(item: &str) -> String {
+    let escaped: String = item
+        .chars()
+        .map(|ch| match ch.is_control() {
+            true => ch.escape_debug().to_string(),
+            false => ch.to_string(),
+        })
+        .collect();
+    text::isolate(&text::sanitize(&escaped))
+}
+
+pub fn run(opts: &MainOptions, filter: &FilterOptions) -> Result<()> {
+    if opts.machine || opts.shell_output {
+        bail!("--machine and --shell-output can't be used, stdout is the filtered items");
+    }
+    if stdin().is_terminal() {
+        bail!("Nothing to filter: pipe the items into confirm filter");
+    }
+    let mut opts = opts.clone();
+    // stdin is the items, so the questions go to the terminal.
+    if opts.tty.is_none() {
+        opts.tty = Some(PathBuf::from("/dev/tty"));
+    }
+    let render = opts.render();
+    let catalog = render.catalog();
+    let default_yes = opts.default == Answer::Yes;
+    let hint = match default_yes {
+        true => format!(
+            "[{}/{}/a(ll)/q]",
+            render.highlight.apply(catalog.y),
+            catalog.n
+        ),
+        false => format!(
+            "[{}/{}/a(ll)/q]",
+            catalog.y,
+            render.highlight.apply(catalog.n)
+        ),
+    };
+    let parse = |line: &str| {
+        let low = line.to_lowercase();
+        match low.as_str() {
+            "" if default_yes => Ok(Reply::Yes),
+            "" => Ok(Reply::No),
+            word if [catalog.yes, catalog.y, "yes", "y"].contains(&word) => Ok(Reply::Yes),
+            word if [catalog.no, catalog.n, "no", "n"].contains(&word) => Ok(Reply::No),
+            "a" | "all" => Ok(Reply::All),
+            "q" | "quit" => Ok(Reply::Quit),
+            _ => Err(fill(
+                catalog.please_type,
+                &[&format!("{}, {}, a", catalog.y, catalog.n), "q"],
+            )),
+        }
+    };
+
+    let separator = match filter.null {
+        true => b'\0',
+        false => b'\n',
+    };
+    // Answering without asking is all or nothing.
+    let mut all = match opts.preset() {
+        Some(Answer::Yes) => true,
+        Some(_) => std::process::exit(1),
+        None => false,
+    };
+    let mut asker = match all {
+        true => None,
+        false => Some(Asker {
+            opts: &opts,
+            terminal: opts.terminal()?,
+        }),
+    };
+    let mut out = stdout().lock();
+    let mut selected = 0;
+    for item in stdin().lock().split(separator) {
+        let item = item?;
+        if item.is_empty() {
+            continue;
+        }
+        if let (false, Some(asker)) = (all, asker.as_mut()) {
+            let question = match filter.prompt.contains("{}") {
+                true => fill(&filter.prompt, &[&shown(&String::from_utf8_lossy(&item))]),
+                false => format!(
+                    "{} {}",
+                    filter.prompt,
+                    shown(&String::from_utf8_lossy(&item))
+                ),
+            };
+            match asker.ask_until(&format!("{} {}: ", question, hint), None, parse)? {
+                Reply::Yes => {}
+                Reply::No => continue,
+                Reply::All => all = true,
+                Reply::Quit => break,
+            }
+        }
+        out.write_all(&item)?;
+        out.write_all(&[separator])?;
+        out.flush()?;
+        selected += 1;
+    }
+    // Like grep, nothing selected is a failure.
+    if selected == 0 {
+        std::process::exit(1);
+    }
+    Ok(())
+}
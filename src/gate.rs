@@ -0,0 +1,77 @@
+//! Pipeline gates: hold back stdin until the user approves passing it on.
+//!
+//! `generate | confirm gate "Apply this?" | apply` reads everything
+//! `generate` writes, shows the start of it on the terminal, and only copies
+//! it to stdout on a "yes".  Otherwise nothing is written, so `apply` sees
+//! empty input, and the exit code fails the pipeline under `set -o
+//! pipefail`.
+
+use std::io::{stdin, stdout, IsTerminal, Read, Write};
+use std::path::PathBuf;
+use std::time::Instant;
+
+use anyhow::{bail, Context, Result};
+use clap::Args;
+
+use crate::{audit, finish, preview, Answer, MainOptions};
+
+#[derive(Clone, Debug, Args)]
+pub struct GateOptions {
+    /// The prompt to display, instead of the one given before the subcommand
+    prompt: Option<String>,
+
+    /// How many lines of the input to show before asking, 0 for none
+    #[arg(long, value_name = "LINES", default_value_t = 10)]
+    preview: usize,
+}
+
+pub fn run(opts: &MainOptions, gate: &GateOptions) -> Result<()> {
+    if opts.machine || opts.shell_output {
+        bail!("--machine and --shell-output can't be used, stdout is the gated input");
+    }
+    if stdin().is_terminal() {
+        bail!("Nothing to gate: pipe the input into confirm gate");
+    }
+    let mut input = Vec::new();
+    stdin()
+        .lock()
+        .read_to_end(&mut input)
+        .context("Cannot read the input to gate")?;
+
+    let mut opts = opts.clone();
+    if let Some(prompt) = &gate.prompt {
+        opts.prompt = prompt.clone();
+    }
+    // stdin is the input, so the question goes to the terminal.
+    if opts.tty.is_none() {
+        opts.tty = Some(PathBuf::from("/dev/tty"));
+    }
+    let neutral_code = opts.neutral_code;
+    let started = Instant::now();
+    let (answer, response) = match opts.preset() {
+        Some(answer) => (answer, None),
+        None => {
+            let mut terminal = opts.terminal()?;
+            if gate.preview > 0 && !input.is_empty() {
+                let text = String::from_utf8_lossy(&input);
+                let excerpt = preview::excerpt(&text, Some(gate.preview));
+                let shown = opts.render().display(&excerpt);
+                write!(terminal.sink, "{}", shown)?;
+            }
+            let answer = opts.clone().confirm_on(terminal).ask_loop().answer();
+            (answer, Some(started.elapsed()))
+        }
+    };
+    if let Err(err) = audit::record(&opts, answer.as_str(), response) {
+        if !opts.quiet {
+            eprintln!("{:#}", err);
+        }
+    }
+    if answer == Answer::Yes {
+        let mut out = stdout().lock();
+        out.write_all(&input)
+            .and_then(|()| out.flush())
+            .context("Cannot pass the input on")?;
+    }
+    finish(answer, neutral_code)
+}
@@ -0,0 +1,80 @@
+//! Sharing one answer between the invocations in a `--group`.
+//!
+//! The first confirm in a group asks as usual and records its answer in a
+//! file named after the group, and the others find it there instead of
+//! asking, so a batch run through xargs or parallel is answered once.  The
+//! file is locked while asking, so invocations starting meanwhile wait for
+//! the answer rather than asking too.  An answer is reused as long as the
+//! group keeps being asked within `--group-window` of the last time.
+
+use std::fs::File;
+use std::io::{Read, Seek, Write};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use anyhow::{Context, Result};
+
+use crate::{lock, Answer, MainOptions};
+
+/// The group's key as part of a file name.
+fn file_suffix(key: &str) -> String {
+    let key: String = key
+        .chars()
+        .map(|ch| match ch {
+            'a'..='z' | 'A'..='Z' | '0'..='9' | '-' | '_' => ch,
+            _ => '_',
+        })
+        .collect();
+    format!("-group-{}", key)
+}
+
+fn now() -> Duration {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+}
+
+/// The recorded answer, if it was given or reused within `window`.
+fn recorded(text: &str, window: Duration) -> Option<Answer> {
+    let (answer, secs) = text.trim().split_once(' ')?;
+    let age = now().checked_sub(Duration::from_secs(secs.parse().ok()?))?;
+    if age > window {
+        log::debug!("The group's answer is {}s old, asking again", age.as_secs());
+        return None;
+    }
+    match answer {
+        "yes" => Some(Answer::Yes),
+        "no" => Some(Answer::No),
+        "neutral" => Some(Answer::Neutral),
+        _ => None,
+    }
+}
+
+fn record(file: &mut File, answer: Answer) -> std::io::Result<()> {
+    file.set_len(0)?;
+    file.rewind()?;
+    writeln!(file, "{} {}", answer.as_str(), now().as_secs())
+}
+
+/// Get the group's answer, asking if there is none to reuse.
+pub fn ask(key: &str, opts: MainOptions) -> Result<Answer> {
+    let path = lock::user_path(&file_suffix(key))?;
+    let window = Duration::from_secs(opts.group_window.into());
+    let mut file = lock::take_private(&path)?;
+    let mut text = String::new();
+    file.read_to_string(&mut text)
+        .with_context(|| format!("Cannot read the group's answer from {}", path.display()))?;
+    let answer = match recorded(&text, window) {
+        Some(answer) => {
+            log::debug!(
+                "Reusing the answer {} given to group {:?}",
+                answer.as_str(),
+                key
+            );
+            answer
+        }
+        None => crate::ask(opts)?,
+    };
+    record(&mut file, answer)
+        .with_context(|| format!("Cannot record the group's answer in {}", path.display()))?;
+    Ok(answer)
+}
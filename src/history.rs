@@ -0,0 +1,81 @@
+//! Answers remembered across runs, one file per question id.
+//!
+//! Files live in `$XDG_STATE_HOME/confirm/history` (or
+//! `~/.local/state/confirm/history`), one entry per line, oldest first.
+
+use std::fs;
+use std::io;
+use std::path::PathBuf;
+
+/// How many entries are kept per question.
+const MAX_ENTRIES: usize = 100;
+
+#[derive(Debug, Default)]
+pub struct History {
+    path: Option<PathBuf>,
+    entries: Vec<String>,
+}
+
+/// Where confirm keeps state between runs, `$XDG_STATE_HOME/confirm`.
+pub fn state_dir() -> Option<PathBuf> {
+    let state = match std::env::var_os("XDG_STATE_HOME") {
+        Some(dir) if !dir.is_empty() => PathBuf::from(dir),
+        _ => PathBuf::from(std::env::var_os("HOME")?).join(".local/state"),
+    };
+    Some(state.join("confirm"))
+}
+
+fn history_dir() -> Option<PathBuf> {
+    Some(state_dir()?.join("history"))
+}
+
+/// Keep ids from escaping the history directory, `.` and `..` included.
+fn file_name(id: &str) -> String {
+    id.chars()
+        .map(|ch| match ch {
+            'a'..='z' | 'A'..='Z' | '0'..='9' | '-' | '_' => ch,
+            _ => '_',
+        })
+        .collect()
+}
+
+impl History {
+    /// Load the history for `id`.  A missing or unreadable file is an empty
+    /// history.
+    pub fn load(id: &str) -> Self {
+        let path = history_dir().map(|dir| dir.join(file_name(id)));
+        let entries = path
+            .as_ref()
+            .and_then(|path| fs::read_to_string(path).ok())
+            .map(|text| text.lines().map(str::to_string).collect())
+            .unwrap_or_default();
+        Self { path, entries }
+    }
+
+    #[cfg(unix)]
+    pub fn entries(&self) -> &[String] {
+        &self.entries
+    }
+
+    /// Remember an answer, moving it to the end if it was already known.
+    pub fn record(&mut self, entry: &str) -> io::Result<()> {
+        if entry.is_empty() || entry.contains('\n') {
+            return Ok(());
+        }
+        self.entries.retain(|known| known != entry);
+        self.entries.push(entry.to_string());
+        let excess = self.entries.len().saturating_sub(MAX_ENTRIES);
+        self.entries.drain(..excess);
+
+        let path = match &self.path {
+            Some(path) => path,
+            None => return Ok(()),
+        };
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        let mut text = self.entries.join("\n");
+        text.push('\n');
+        fs::write(path, text)
+    }
+}
@@ -0,0 +1,176 @@
+//! Installing git hooks that ask before committing or pushing.
+//!
+//! The hook is a small shell script that runs this executable with its
+//! prompt on `/dev/tty`, since git hooks don't get the terminal on stdin.
+//! Without a terminal, like in a GUI client, the hook refuses rather than
+//! letting the commit or push through unasked; `git commit --no-verify` and
+//! `git push --no-verify` skip it.
+
+use std::fs;
+use std::path::PathBuf;
+use std::process::Command;
+
+use anyhow::{anyhow, bail, Context, Result};
+use clap::{Args, Subcommand, ValueEnum};
+
+use crate::shell::quote;
+
+/// Marks hooks written by us, so others are never overwritten or removed.
+const MARKER: &str = "# Installed by confirm hook install.";
+
+#[derive(Clone, Debug, Args)]
+pub struct HookOptions {
+    #[command(subcommand)]
+    action: Action,
+}
+
+#[derive(Clone, Debug, Subcommand)]
+enum Action {
+    /// Write a hook that asks before going ahead
+    Install {
+        kind: Kind,
+
+        /// The question to ask, instead of one naming the branch
+        #[arg(long)]
+        prompt: Option<String>,
+
+        /// Replace a hook that wasn't installed by confirm
+        #[arg(long)]
+        force: bool,
+    },
+    /// Remove a hook installed by confirm
+    Uninstall { kind: Kind },
+}
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq, ValueEnum)]
+enum Kind {
+    /// Ask before each push
+    PrePush,
+    /// Ask before each commit
+    PreCommit,
+}
+
+impl Kind {
+    fn file_name(self) -> &'static str {
+        match self {
+            Kind::PrePush => "pre-push",
+            Kind::PreCommit => "pre-commit",
+        }
+    }
+
+    /// The default question, as a double-quoted shell word expanded when the
+    /// hook runs.
+    fn default_prompt(self) -> &'static str {
+        match self {
+            // pre-push gets the remote as $1 and the refs on stdin.
+            Kind::PrePush => r#""Push ${branches}to $1?""#,
+            Kind::PreCommit => r#""Commit to $(git branch --show-current)?""#,
+        }
+    }
+}
+
+/// The hooks directory of the current repository, respecting
+/// `core.hooksPath`.
+fn hooks_dir() -> Result<PathBuf> {
+    let output = Command::new("git")
+        .args(["rev-parse", "--git-path", "hooks"])
+        .output()
+        .context("Cannot run git")?;
+    if !output.status.success() {
+        bail!(
+            "Cannot find the hooks directory: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        );
+    }
+    Ok(PathBuf::from(
+        String::from_utf8_lossy(&output.stdout).trim(),
+    ))
+}
+
+fn script(kind: Kind, prompt: Option<&str>) -> Result<String> {
+    let exe = std::env::current_exe().context("Cannot find the confirm executable")?;
+    let exe = exe
+        .to_str()
+        .ok_or_else(|| anyhow!("The path to confirm isn't valid UTF-8"))?;
+    let prompt = match prompt {
+        Some(prompt) => quote(prompt),
+        None => kind.default_prompt().to_string(),
+    };
+    let branches = match kind {
+        Kind::PrePush => {
+            "branches=$(while read -r _ _ ref _; do printf '%s ' \"${ref#refs/heads/}\"; done)\n"
+        }
+        Kind::PreCommit => "",
+    };
+    Ok(format!(
+        "#!/bin/sh\n\
+         {marker}\n\
+         # Remove with: confirm hook uninstall {name}\n\
+         {branches}\
+         if ! {{ true < /dev/tty; }} 2>/dev/null; then\n\
+         \x20   echo \"No terminal to confirm on, skip this hook with --no-verify\" >&2\n\
+         \x20   exit 1\n\
+         fi\n\
+         exec {exe} --default no {prompt} < /dev/tty\n",
+        marker = MARKER,
+        name = kind.file_name(),
+        branches = branches,
+        exe = quote(exe),
+        prompt = prompt,
+    ))
+}
+
+/// Whether the hook at `path` exists but isn't one of ours.
+fn is_foreign(path: &std::path::Path) -> Result<bool> {
+    match fs::read_to_string(path) {
+        Ok(text) => Ok(!text.contains(MARKER)),
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => Ok(false),
+        // Unreadable or not text, so certainly not ours.
+        Err(err) if err.kind() == std::io::ErrorKind::InvalidData => Ok(true),
+        Err(err) => Err(anyhow!("Cannot read {}: {}", path.display(), err)),
+    }
+}
+
+pub fn run(hook: &HookOptions) -> Result<()> {
+    match &hook.action {
+        Action::Install {
+            kind,
+            prompt,
+            force,
+        } => {
+            let dir = hooks_dir()?;
+            let path = dir.join(kind.file_name());
+            if !force && is_foreign(&path)? {
+                bail!(
+                    "{} already exists and wasn't installed by confirm, use --force to replace it",
+                    path.display()
+                );
+            }
+            fs::create_dir_all(&dir).with_context(|| format!("Cannot create {}", dir.display()))?;
+            fs::write(&path, script(*kind, prompt.as_deref())?)
+                .with_context(|| format!("Cannot write {}", path.display()))?;
+            #[cfg(unix)]
+            {
+                use std::os::unix::fs::PermissionsExt;
+                fs::set_permissions(&path, fs::Permissions::from_mode(0o755))
+                    .with_context(|| format!("Cannot make {} executable", path.display()))?;
+            }
+            println!("Installed {}", path.display());
+        }
+        Action::Uninstall { kind } => {
+            let path = hooks_dir()?.join(kind.file_name());
+            if !path.exists() {
+                bail!("There is no {} hook", kind.file_name());
+            }
+            if is_foreign(&path)? {
+                bail!(
+                    "{} wasn't installed by confirm, leaving it alone",
+                    path.display()
+                );
+            }
+            fs::remove_file(&path).with_context(|| format!("Cannot remove {}", path.display()))?;
+            println!("Removed {}", path.display());
+        }
+    }
+    Ok(())
+}
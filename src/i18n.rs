@@ -0,0 +1,248 @@
+//! Translations of the built-in words and messages.
+//!
+//! Catalogs are compiled in, one per language.  The English words are
+//! accepted as answers in every language, since they're what people reach
+//! for when unsure.
+
+use std::str::FromStr;
+
+use crate::ConfirmError;
+
+/// A language with a message catalog.
+#[derive(Debug, Copy, Clone, Default, Eq, PartialEq)]
+pub enum Lang {
+    #[default]
+    English,
+    German,
+    French,
+    Spanish,
+}
+
+/// The words and messages shown to the user.  Messages with `{}` have it
+/// replaced by their arguments in order, see [`fill`].
+#[derive(Debug)]
+pub struct Catalog {
+    pub yes: &'static str,
+    pub no: &'static str,
+    /// Single-letter answers.
+    pub y: &'static str,
+    pub n: &'static str,
+    /// Accessible hint when answering with a keypress, given the yes and
+    /// no keys.
+    pub press: &'static str,
+    /// Accessible hint when typing an answer, given the yes and no words.
+    pub type_answer: &'static str,
+    pub default_yes: &'static str,
+    pub default_no: &'static str,
+    pub no_default: &'static str,
+    /// Accessible hint for the neutral answer, given the answer.
+    pub neutral: &'static str,
+    /// Accessible warning in front of a destructive question.
+    pub danger: &'static str,
+    /// Given the yes and no words.
+    pub please_type: &'static str,
+    /// When a validator rejects the answer.
+    pub not_accepted: &'static str,
+    pub read_error: &'static str,
+    /// Given the reason.
+    pub keys_unsupported: &'static str,
+    pub input_closed: &'static str,
+    pub retries_exceeded: &'static str,
+    pub not_a_tty: &'static str,
+    pub no_terminal: &'static str,
+    /// Given how long it has been.
+    pub still_waiting: &'static str,
+    pub waiting: &'static str,
+    /// Given how long it waited.
+    pub timed_out: &'static str,
+    /// Given how many attempts are left, when more than one.
+    pub tries_left: &'static str,
+    pub last_try: &'static str,
+    /// Given how long until going ahead after a yes.
+    pub proceeding: &'static str,
+    pub cancelled: &'static str,
+}
+
+const ENGLISH: Catalog = Catalog {
+    yes: "yes",
+    no: "no",
+    y: "y",
+    n: "n",
+    press: "Press {} for yes or {} for no.",
+    type_answer: "Type {} for yes or {} for no.",
+    default_yes: "The default is yes.",
+    default_no: "The default is no.",
+    no_default: "There is no default.",
+    neutral: "{} defers the decision.",
+    danger: "Warning, this cannot be undone:",
+    please_type: "Please type {} or {}",
+    not_accepted: "That answer isn't accepted",
+    read_error: "Error while reading user input: {}",
+    keys_unsupported: "Cannot read single keys ({}), press Enter after the answer.",
+    input_closed: "Input closed.  Aborting...",
+    retries_exceeded: "Retry count exceeded.  Aborting...",
+    not_a_tty: "Warning: using confirm when stdin is not a tty is not supported.",
+    no_terminal: "No terminal to ask on.  Aborting...",
+    still_waiting: "Still waiting for an answer after {}.",
+    waiting: "(waiting {})",
+    timed_out: "No answer after {}, using the default.",
+    tries_left: "({} tries left)",
+    last_try: "(last try)",
+    proceeding: "Proceeding in {}, press any key to cancel",
+    cancelled: "Cancelled.",
+};
+
+const GERMAN: Catalog = Catalog {
+    yes: "ja",
+    no: "nein",
+    y: "j",
+    n: "n",
+    press: "Drücken Sie {} für ja oder {} für nein.",
+    type_answer: "Geben Sie {} für ja oder {} für nein ein.",
+    default_yes: "Die Vorgabe ist ja.",
+    default_no: "Die Vorgabe ist nein.",
+    no_default: "Es gibt keine Vorgabe.",
+    neutral: "{} verschiebt die Entscheidung.",
+    danger: "Achtung, dies kann nicht rückgängig gemacht werden:",
+    please_type: "Bitte {} oder {} eingeben",
+    not_accepted: "Diese Antwort wird nicht akzeptiert",
+    read_error: "Fehler beim Lesen der Eingabe: {}",
+    keys_unsupported:
+        "Einzelne Tasten können nicht gelesen werden ({}), bitte die Antwort mit Enter bestätigen.",
+    input_closed: "Eingabe geschlossen.  Abbruch...",
+    retries_exceeded: "Zu viele Versuche.  Abbruch...",
+    not_a_tty: "Warnung: confirm wird ohne Terminal auf stdin nicht unterstützt.",
+    no_terminal: "Kein Terminal für die Frage.  Abbruch...",
+    still_waiting: "Warte seit {} auf eine Antwort.",
+    waiting: "(wartet seit {})",
+    timed_out: "Keine Antwort nach {}, die Vorgabe wird verwendet.",
+    tries_left: "(noch {} Versuche)",
+    last_try: "(letzter Versuch)",
+    proceeding: "Weiter in {}, zum Abbrechen eine Taste drücken",
+    cancelled: "Abgebrochen.",
+};
+
+const FRENCH: Catalog = Catalog {
+    yes: "oui",
+    no: "non",
+    y: "o",
+    n: "n",
+    press: "Appuyez sur {} pour oui ou {} pour non.",
+    type_answer: "Tapez {} pour oui ou {} pour non.",
+    default_yes: "La réponse par défaut est oui.",
+    default_no: "La réponse par défaut est non.",
+    no_default: "Il n'y a pas de réponse par défaut.",
+    neutral: "{} reporte la décision.",
+    danger: "Attention, cette action est irréversible :",
+    please_type: "Veuillez taper {} ou {}",
+    not_accepted: "Cette réponse n'est pas acceptée",
+    read_error: "Erreur de lecture de la réponse : {}",
+    keys_unsupported:
+        "Impossible de lire les touches une à une ({}), validez la réponse avec Entrée.",
+    input_closed: "Entrée fermée.  Abandon...",
+    retries_exceeded: "Nombre d'essais dépassé.  Abandon...",
+    not_a_tty: "Attention : confirm n'est pas pris en charge si stdin n'est pas un terminal.",
+    no_terminal: "Aucun terminal pour poser la question.  Abandon...",
+    still_waiting: "Toujours en attente d'une réponse après {}.",
+    waiting: "(attente {})",
+    timed_out: "Pas de réponse après {}, la réponse par défaut est utilisée.",
+    tries_left: "({} essais restants)",
+    last_try: "(dernier essai)",
+    proceeding: "Poursuite dans {}, appuyez sur une touche pour annuler",
+    cancelled: "Annulé.",
+};
+
+const SPANISH: Catalog = Catalog {
+    yes: "sí",
+    no: "no",
+    y: "s",
+    n: "n",
+    press: "Pulse {} para sí o {} para no.",
+    type_answer: "Escriba {} para sí o {} para no.",
+    default_yes: "La respuesta predeterminada es sí.",
+    default_no: "La respuesta predeterminada es no.",
+    no_default: "No hay respuesta predeterminada.",
+    neutral: "{} aplaza la decisión.",
+    danger: "Atención, esto no se puede deshacer:",
+    please_type: "Escriba {} o {}",
+    not_accepted: "Esa respuesta no se acepta",
+    read_error: "Error al leer la respuesta: {}",
+    keys_unsupported: "No se pueden leer teclas sueltas ({}), pulse Intro tras la respuesta.",
+    input_closed: "Entrada cerrada.  Cancelando...",
+    retries_exceeded: "Demasiados intentos.  Cancelando...",
+    not_a_tty: "Aviso: confirm no admite que stdin no sea un terminal.",
+    no_terminal: "No hay terminal para preguntar.  Cancelando...",
+    still_waiting: "Sigue esperando una respuesta tras {}.",
+    waiting: "(esperando {})",
+    timed_out: "Sin respuesta tras {}, se usa la respuesta predeterminada.",
+    tries_left: "(quedan {} intentos)",
+    last_try: "(último intento)",
+    proceeding: "Continuando en {}, pulse una tecla para cancelar",
+    cancelled: "Cancelado.",
+};
+
+impl Lang {
+    /// The ISO 639-1 code, like "de".
+    pub fn code(self) -> &'static str {
+        match self {
+            Lang::English => "en",
+            Lang::German => "de",
+            Lang::French => "fr",
+            Lang::Spanish => "es",
+        }
+    }
+
+    pub fn catalog(self) -> &'static Catalog {
+        match self {
+            Lang::English => &ENGLISH,
+            Lang::German => &GERMAN,
+            Lang::French => &FRENCH,
+            Lang::Spanish => &SPANISH,
+        }
+    }
+
+    /// The language of the locale, following the usual `LC_ALL` >
+    /// `LC_MESSAGES` > `LANG` precedence, English if it has no catalog.
+    pub fn from_env() -> Self {
+        ["LC_ALL", "LC_MESSAGES", "LANG"]
+            .iter()
+            .filter_map(|var| std::env::var(var).ok())
+            .find(|value| !value.is_empty())
+            .and_then(|locale| locale.parse().ok())
+            .unwrap_or_default()
+    }
+}
+
+impl FromStr for Lang {
+    type Err = ConfirmError;
+
+    /// Parse a language code or a locale name like `de_DE.UTF-8`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let code = s
+            .split(['_', '-', '.', '@'])
+            .next()
+            .unwrap_or_default()
+            .to_ascii_lowercase();
+        match code.as_str() {
+            "en" | "c" | "posix" => Ok(Lang::English),
+            "de" => Ok(Lang::German),
+            "fr" => Ok(Lang::French),
+            "es" => Ok(Lang::Spanish),
+            _ => Err(ConfirmError::UnknownLanguage(s.to_string())),
+        }
+    }
+}
+
+/// Replace each `{}` in `template` with the next argument.
+pub fn fill(template: &str, args: &[&str]) -> String {
+    let mut out = String::with_capacity(template.len());
+    let mut args = args.iter();
+    let mut rest = template;
+    while let Some(at) = rest.find("{}") {
+        out.push_str(&rest[..at]);
+        out.push_str(args.next().copied().unwrap_or_default());
+        rest = &rest[at + 2..];
+    }
+    out.push_str(rest);
+    out
+}
@@ -0,0 +1,809 @@
+//! The prompt logic behind the `confirm` tool: answer parsing, retries and
+//! defaults, independent of where the question is shown.
+//!
+//! Input and output go through a [`Backend`], so the same logic runs on a
+//! real terminal, in a WASI runtime or behind a web-based terminal.  The
+//! [`Streams`] backend covers anything with a reader and a writer, and
+//! [`Callback`] turns a function into a writer, so prompts can be drawn by
+//! the embedding program instead of going to the process's own output.
+//!
+//! Debug diagnostics, like why an answer was rejected, go through the `log`
+//! crate.
+
+use std::convert::Infallible;
+use std::fmt;
+use std::io;
+use std::num::NonZeroU8;
+use std::str::FromStr;
+use std::sync::Arc;
+
+mod backend;
+mod error;
+pub mod events;
+pub mod i18n;
+#[cfg(feature = "serde")]
+mod prompt_config;
+pub mod text;
+
+pub use backend::{Backend, Callback, Streams};
+pub use error::ConfirmError;
+use events::Event;
+use i18n::{fill, Catalog, Lang};
+#[cfg(feature = "serde")]
+pub use prompt_config::PromptConfig;
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+#[cfg_attr(feature = "cli", derive(clap::ValueEnum))]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(rename_all = "lowercase")
+)]
+pub enum Answer {
+    /// Treat an empty answer as "yes"
+    Yes,
+    /// Treat an empty answer as "no"
+    No,
+    /// Ask again on an empty answer
+    Retry,
+    /// The configured third answer, see [`Question::neutral`]
+    #[cfg_attr(feature = "cli", value(skip))]
+    Neutral,
+}
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum ReaderType {
+    SingleChar,
+    NewlineBuffered,
+}
+
+/// How the default answer stands out in the hint.
+#[derive(Debug, Copy, Clone, Default, Eq, PartialEq)]
+#[cfg_attr(feature = "cli", derive(clap::ValueEnum))]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(rename_all = "lowercase")
+)]
+pub enum Highlight {
+    /// Capital letters, as in "[Y/n]"
+    #[default]
+    Uppercase,
+    /// Bold text
+    Bold,
+    /// Underlined text
+    Underline,
+    /// Colored text
+    Color,
+    /// A marker in front, as in "[▸y/n]"
+    Arrow,
+}
+
+impl Highlight {
+    /// Emphasize `word` as the default answer.
+    pub fn apply(self, word: &str) -> String {
+        match self {
+            Highlight::Uppercase => word.to_uppercase(),
+            Highlight::Bold => format!("\x1b[1m{}\x1b[0m", word),
+            Highlight::Underline => format!("\x1b[4m{}\x1b[0m", word),
+            Highlight::Color => format!("\x1b[36m{}\x1b[0m", word),
+            Highlight::Arrow => format!("\u{25B8}{}", word),
+        }
+    }
+}
+
+/// Symbols in front of the prompt: one while asking, and one for each final
+/// answer once the question has been answered.
+#[derive(Debug, Clone, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Icons {
+    pub asking: String,
+    pub yes: String,
+    pub no: String,
+}
+
+impl Default for Icons {
+    fn default() -> Self {
+        Self {
+            asking: "?".to_string(),
+            yes: "\u{2714}".to_string(),
+            no: "\u{2716}".to_string(),
+        }
+    }
+}
+
+impl FromStr for Icons {
+    type Err = ConfirmError;
+
+    /// Parse the three symbols separated by commas, like `?,✔,✖`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.split(',').collect::<Vec<_>>().as_slice() {
+            [asking, yes, no] => Ok(Self {
+                asking: asking.to_string(),
+                yes: yes.to_string(),
+                no: no.to_string(),
+            }),
+            _ => Err(ConfirmError::InvalidIcons(s.to_string())),
+        }
+    }
+}
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum TryMode {
+    Infinite,
+    Count(NonZeroU8),
+    /// Ask a single time, without retries.
+    Once,
+}
+
+impl FromStr for Answer {
+    type Err = Infallible;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let low = s.to_ascii_lowercase();
+        match low.as_str() {
+            "yes" | "y" => Ok(Answer::Yes),
+            "no" | "n" => Ok(Answer::No),
+            _ => Ok(Answer::Retry),
+        }
+    }
+}
+
+impl Answer {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Answer::Yes => "yes",
+            Answer::No => "no",
+            Answer::Retry => "retry",
+            Answer::Neutral => "neutral",
+        }
+    }
+}
+
+/// How asking a question ended.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(rename_all = "kebab-case")
+)]
+pub enum Decision {
+    Yes,
+    No,
+    /// The configured third answer, see [`Question::neutral`].
+    Neutral,
+    /// The backend gave up on the question, by failing a read with
+    /// [`Interrupted`](io::ErrorKind::Interrupted).
+    Aborted,
+    /// No answer came in time, the backend failed a read with
+    /// [`TimedOut`](io::ErrorKind::TimedOut), and the default was taken.
+    /// Holds the default, or "no" without one.
+    TimedOut(Answer),
+    /// Every attempt was answered with something that isn't an answer.
+    RetriesExhausted,
+    /// The input was closed before an answer was given.
+    InputClosed,
+}
+
+impl Decision {
+    /// The answer this counts as: the one given or timed out to, and "no"
+    /// for anything else.
+    pub fn answer(self) -> Answer {
+        match self {
+            Decision::Yes => Answer::Yes,
+            Decision::Neutral => Answer::Neutral,
+            Decision::TimedOut(answer) => answer,
+            _ => Answer::No,
+        }
+    }
+}
+
+impl From<Answer> for Decision {
+    /// The decision for an answer that was given, with a retry counting as
+    /// running out of them.
+    fn from(answer: Answer) -> Self {
+        match answer {
+            Answer::Yes => Decision::Yes,
+            Answer::No => Decision::No,
+            Answer::Neutral => Decision::Neutral,
+            Answer::Retry => Decision::RetriesExhausted,
+        }
+    }
+}
+
+impl TryMode {
+    /// How many times a question is asked in total, `None` for no limit.
+    /// A count of N asks N times, the first question included.
+    pub fn total_asks(self) -> Option<u32> {
+        match self {
+            TryMode::Infinite => None,
+            TryMode::Count(x) => Some(u32::from(x.get())),
+            TryMode::Once => Some(1),
+        }
+    }
+}
+
+impl From<u8> for TryMode {
+    fn from(count: u8) -> Self {
+        match NonZeroU8::new(count) {
+            Some(nz) => TryMode::Count(nz),
+            None => TryMode::Infinite,
+        }
+    }
+}
+
+/// Whether the user opted out of colored output with `NO_COLOR` (see
+/// https://no-color.org) or `CLICOLOR=0`, or the terminal is too dumb for
+/// it, unless `FORCE_COLOR` or `CLICOLOR_FORCE` asks for color anyway.
+pub fn no_color() -> bool {
+    let set = |var| std::env::var(var).is_ok_and(|value| !value.is_empty());
+    let is = |var, expected| std::env::var(var).is_ok_and(|value| value == expected);
+    let forced = (set("FORCE_COLOR") && !is("FORCE_COLOR", "0"))
+        || (set("CLICOLOR_FORCE") && !is("CLICOLOR_FORCE", "0"));
+    !forced && (set("NO_COLOR") || is("CLICOLOR", "0") || dumb_terminal())
+}
+
+/// Whether the terminal can't be trusted with escape sequences or terminal
+/// modes: `TERM=dumb`, as in Emacs' shell mode and on some serial consoles,
+/// or no `TERM` at all on Unix, as in many CI logs.
+pub fn dumb_terminal() -> bool {
+    match std::env::var("TERM") {
+        Ok(term) => term.is_empty() || term == "dumb",
+        Err(_) => cfg!(unix),
+    }
+}
+
+/// Whether the locale asks for a character set other than UTF-8, following
+/// the usual `LC_ALL` > `LC_CTYPE` > `LANG` precedence.  An unset locale
+/// isn't taken as a sign either way.
+pub fn locale_is_ascii_only() -> bool {
+    let locale = ["LC_ALL", "LC_CTYPE", "LANG"]
+        .iter()
+        .filter_map(|var| std::env::var(var).ok())
+        .find(|value| !value.is_empty());
+    match locale {
+        Some(locale) => {
+            let locale = locale.to_ascii_lowercase();
+            !(locale.contains("utf-8") || locale.contains("utf8"))
+        }
+        None => false,
+    }
+}
+
+/// Replace non-ASCII characters with their closest ASCII spelling, or `?`
+/// when there's no sensible one.
+pub fn degrade_to_ascii(s: &str) -> String {
+    let mut out = String::with_capacity(s.len());
+    for ch in s.chars() {
+        let replacement = match ch {
+            _ if ch.is_ascii() => {
+                out.push(ch);
+                continue;
+            }
+            '\u{2018}' | '\u{2019}' | '\u{201A}' | '\u{2032}' => "'",
+            '\u{201C}' | '\u{201D}' | '\u{201E}' | '\u{00AB}' | '\u{00BB}' => "\"",
+            '\u{2010}'..='\u{2015}' | '\u{2212}' => "-",
+            '\u{2026}' => "...",
+            '\u{2022}' | '\u{00B7}' => "*",
+            '\u{2190}' => "<-",
+            '\u{2192}' => "->",
+            '\u{25B8}' | '\u{203A}' => ">",
+            '\u{2713}' | '\u{2714}' => "v",
+            '\u{2716}' | '\u{2717}' | '\u{2718}' | '\u{00D7}' => "x",
+            '\u{00A0}' | '\u{2000}'..='\u{200A}' => " ",
+            'à'..='å' => "a",
+            'À'..='Å' => "A",
+            'ç' => "c",
+            'Ç' => "C",
+            'è'..='ë' => "e",
+            'È'..='Ë' => "E",
+            'ì'..='ï' => "i",
+            'Ì'..='Ï' => "I",
+            'ñ' => "n",
+            'Ñ' => "N",
+            'ò'..='ö' | 'ø' => "o",
+            'Ò'..='Ö' | 'Ø' => "O",
+            'ù'..='ü' => "u",
+            'Ù'..='Ü' => "U",
+            'ß' => "ss",
+            '\u{00BF}' | '\u{00A1}' => "",
+            '\u{200E}' | '\u{200F}' | '\u{2066}'..='\u{2069}' => "",
+            _ => "?",
+        };
+        out.push_str(replacement);
+    }
+    out
+}
+
+/// Custom acceptance logic, deciding what a typed answer means, see
+/// [`Question::validator`].
+#[derive(Clone)]
+pub struct Validator(Arc<Check>);
+
+type Check = dyn Fn(&str) -> Option<Answer> + Send + Sync;
+
+impl Validator {
+    pub fn new(check: impl Fn(&str) -> Option<Answer> + Send + Sync + 'static) -> Self {
+        Self(Arc::new(check))
+    }
+
+    /// What `response` means, or `None` to go by the accepted words.
+    pub fn check(&self, response: &str) -> Option<Answer> {
+        (self.0)(response)
+    }
+}
+
+impl fmt::Debug for Validator {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("Validator")
+    }
+}
+
+/// Answers taken as yes and no with [`Question::boolean_aliases`].
+const TRUE_ALIASES: &[&str] = &["1", "true", "on"];
+const FALSE_ALIASES: &[&str] = &["0", "false", "off"];
+
+/// What changes while a question is being asked.
+struct Asking {
+    prompt: String,
+    reader_type: ReaderType,
+    /// What the user typed for the last answer, as echoed by the terminal.
+    typed: String,
+    /// Ask again on the same line, with the problem shown in front of the
+    /// prompt rather than as a message of its own.
+    in_place: bool,
+    /// Why the last answer wasn't accepted, when asking in place.
+    error: Option<String>,
+}
+
+/// A yes/no question and the rules for answering it.
+#[derive(Debug, Clone)]
+pub struct Question {
+    pub prompt: String,
+    /// The answer used when the response is empty.
+    pub default: Answer,
+    pub reader_type: ReaderType,
+    pub retry_mode: TryMode,
+    /// Require "yes" or "no" spelled out, not single letters.
+    pub full_words: bool,
+    /// Also accept the usual spellings of booleans in configuration files:
+    /// "1", "true" and "on" for yes, "0", "false" and "off" for no.
+    pub boolean_aliases: bool,
+    /// Complete plain-text lines with the accepted answers spelled out, no
+    /// redrawing and no color-only cues.
+    pub accessible: bool,
+    /// Only ever print ASCII, for terminals that can't show anything else.
+    pub ascii: bool,
+    /// The language of the hint, the accepted answers and the messages.
+    pub lang: Lang,
+    /// How the default stands out in the hint.  Accessible hints spell the
+    /// default out instead.
+    pub highlight: Highlight,
+    /// Symbols in front of the prompt.  Not used in accessible mode.
+    pub icons: Option<Icons>,
+    /// A third accepted answer, like "later", that is neither yes nor no.
+    /// It must differ from the yes and no answers, and be a single
+    /// character when reading single keys.
+    pub neutral: Option<String>,
+    /// Mark the question as destructive: the prompt is shown in bold red
+    /// (just bold with `NO_COLOR`), or announced as a warning in accessible
+    /// mode.
+    pub danger: bool,
+    /// Text that has to be typed exactly to answer yes, like the name of
+    /// what is about to be deleted.  It replaces the yes words.
+    pub require_match: Option<String>,
+    /// How the remaining attempts are shown when asking again, with `{}`
+    /// for the number, like "({} tries left)".  The language's wording is
+    /// used if unset, and an empty format shows nothing.
+    pub tries_format: Option<String>,
+    /// Decides what a non-empty answer means instead of the accepted words,
+    /// returning [`Answer::Retry`] to ask again, or `None` to leave it to
+    /// the words after all.  Empty answers still get the default.
+    pub validator: Option<Validator>,
+}
+
+impl Question {
+    /// A question with the same defaults as the command line tool.
+    pub fn new(prompt: impl Into<String>) -> Self {
+        Self {
+            prompt: prompt.into(),
+            default: Answer::Retry,
+            reader_type: ReaderType::NewlineBuffered,
+            retry_mode: TryMode::from(3),
+            full_words: false,
+            boolean_aliases: false,
+            accessible: false,
+            ascii: false,
+            lang: Lang::English,
+            highlight: Highlight::Uppercase,
+            icons: None,
+            neutral: None,
+            danger: false,
+            require_match: None,
+            tries_format: None,
+            validator: None,
+        }
+    }
+
+    fn catalog(&self) -> &'static Catalog {
+        self.lang.catalog()
+    }
+
+    /// The yes and no answers as shown in hints.
+    fn words(&self) -> (String, &'static str) {
+        let catalog = self.catalog();
+        let (yes, no) = match self.full_words {
+            true => (catalog.yes, catalog.no),
+            false => (catalog.y, catalog.n),
+        };
+        match &self.require_match {
+            Some(text) => (text::sanitize(text), no),
+            None => (yes.to_string(), no),
+        }
+    }
+
+    fn render_option_box(&self) -> String {
+        let (yes, no) = self.words();
+        let options = match self.default {
+            Answer::Yes => format!("{}/{}", self.highlight.apply(&yes), no),
+            Answer::No => format!("{}/{}", yes, self.highlight.apply(no)),
+            Answer::Retry | Answer::Neutral => format!("{}/{}", yes, no),
+        };
+        match &self.neutral {
+            Some(neutral) => format!("[{}/{}]", options, neutral),
+            None => format!("[{}]", options),
+        }
+    }
+
+    /// Spell out the accepted answers as a sentence, for screen readers.
+    fn render_option_list(&self) -> String {
+        let catalog = self.catalog();
+        let (yes, no) = self.words();
+        let hint = match self.reader_type {
+            ReaderType::SingleChar => catalog.press,
+            ReaderType::NewlineBuffered => catalog.type_answer,
+        };
+        let default = match self.default {
+            Answer::Yes => catalog.default_yes,
+            Answer::No => catalog.default_no,
+            Answer::Retry | Answer::Neutral => catalog.no_default,
+        };
+        match &self.neutral {
+            Some(neutral) => format!(
+                "{} {} {}",
+                fill(hint, &[&yes, no]),
+                fill(catalog.neutral, &[neutral]),
+                default
+            ),
+            None => format!("{} {}", fill(hint, &[&yes, no]), default),
+        }
+    }
+
+    /// Make sense of a non-empty response.  The English words are accepted
+    /// in any language, and so are ASCII spellings like "si" for "sí".
+    fn parse(&self, response: &str) -> Answer {
+        let catalog = self.catalog();
+        let low = response.to_lowercase();
+        let is = |word: &str| low == word || low == degrade_to_ascii(word);
+        let alias = |aliases: &[&str]| self.boolean_aliases && aliases.contains(&low.as_str());
+        let yes = is(catalog.yes) || low == "yes" || alias(TRUE_ALIASES);
+        let no = is(catalog.no) || low == "no" || alias(FALSE_ALIASES);
+        let neutral = self
+            .neutral
+            .as_ref()
+            .is_some_and(|neutral| low == neutral.to_lowercase());
+        if let Some(text) = &self.require_match {
+            return match response {
+                _ if neutral => Answer::Neutral,
+                response if response == text => Answer::Yes,
+                _ if no || (!self.full_words && (is(catalog.n) || low == "n")) => Answer::No,
+                _ => Answer::Retry,
+            };
+        }
+        match self.full_words {
+            _ if neutral => Answer::Neutral,
+            _ if yes => Answer::Yes,
+            _ if no => Answer::No,
+            true => Answer::Retry,
+            false if is(catalog.y) || low == "y" => Answer::Yes,
+            false if is(catalog.n) || low == "n" => Answer::No,
+            false => Answer::Retry,
+        }
+    }
+
+    /// The prompt as shown to the user, with the accepted answers added.
+    pub fn render_prompt(&self) -> String {
+        self.render_retry_prompt(None, None)
+    }
+
+    /// The prompt when asking again with `remaining` attempts left,
+    /// including this one, after `error`.
+    fn render_retry_prompt(&self, remaining: Option<u32>, error: Option<&str>) -> String {
+        let text = self.emphasize(&self.prompt);
+        let hint = match self.accessible {
+            true => self.render_option_list(),
+            false => self.render_option_box(),
+        };
+        let hint = match remaining.and_then(|remaining| self.render_tries_left(remaining)) {
+            Some(tries) => format!("{} {}", hint, tries),
+            None => hint,
+        };
+        let prompt = match (self.accessible, &self.icons) {
+            (true, _) if self.danger => {
+                format!("{} {} {}\n", self.catalog().danger, text, hint)
+            }
+            (true, _) => format!("{} {}\n", text, hint),
+            (false, Some(icons)) => format!("{} {} {}: ", icons.asking, text, hint),
+            (false, None) => format!("{} {}: ", text, hint),
+        };
+        match error {
+            Some(error) if no_color() => self.display(format!("{}. {}", error, prompt)),
+            Some(error) => self.display(format!("\x1b[31m{}.\x1b[0m {}", error, prompt)),
+            None => self.display(prompt),
+        }
+    }
+
+    fn render_tries_left(&self, remaining: u32) -> Option<String> {
+        let catalog = self.catalog();
+        let count = remaining.to_string();
+        match &self.tries_format {
+            Some(format) if format.is_empty() => None,
+            Some(format) => Some(fill(format, &[&count])),
+            None if remaining == 1 => Some(catalog.last_try.to_string()),
+            None => Some(fill(catalog.tries_left, &[&count])),
+        }
+    }
+
+    /// The prompt text, sanitized, isolated and styled for showing.  A dumb
+    /// terminal gets the warning spelled out instead of bold text.
+    fn emphasize(&self, prompt: &str) -> String {
+        let text = text::isolate(&text::sanitize(prompt));
+        match self.danger && !self.accessible {
+            true if dumb_terminal() => format!("{} {}", self.catalog().danger, text),
+            true if no_color() => format!("\x1b[1m{}\x1b[0m", text),
+            true => format!("\x1b[1;31m{}\x1b[0m", text),
+            false => text,
+        }
+    }
+
+    fn display(&self, text: String) -> String {
+        match self.ascii {
+            true => degrade_to_ascii(&text),
+            false => text,
+        }
+    }
+
+    /// Replace the prompt line with the final answer and its icon, if the
+    /// backend allows rewriting what it has shown.
+    fn show_outcome(&self, backend: &mut impl Backend, asking: &Asking, answer: Answer) {
+        let icons = match &self.icons {
+            Some(icons) if !self.accessible && backend.can_redraw() => icons,
+            _ => return,
+        };
+        let catalog = self.catalog();
+        let (icon, word) = match (answer, &self.neutral) {
+            (Answer::Yes, _) => (&icons.yes, catalog.yes),
+            (Answer::Neutral, Some(neutral)) => (&icons.asking, neutral.as_str()),
+            _ => (&icons.no, catalog.no),
+        };
+        let line = self.display(format!(
+            "{} {} {}",
+            icon,
+            self.emphasize(&self.prompt),
+            word
+        ));
+        // The answer stands even if this can't be written.
+        let _ = backend.show(&format!("{}{}\n", Self::erase(backend, asking), line));
+    }
+
+    /// Move up to where the last prompt starts, which may have wrapped, and
+    /// clear everything after it.
+    fn erase(backend: &impl Backend, asking: &Asking) -> String {
+        let shown = text::display_width(&asking.prompt) + text::display_width(&asking.typed);
+        let rows = backend
+            .columns()
+            .map_or(1, |columns| text::rows(shown, columns));
+        format!("\x1b[{}A\r\x1b[J", rows)
+    }
+
+    /// Read one raw answer, returning `None` once the input has been closed.
+    /// Switches to reading lines if the backend can't read single keys.
+    fn read_raw(
+        &self,
+        backend: &mut impl Backend,
+        asking: &mut Asking,
+    ) -> io::Result<Option<String>> {
+        asking.typed.clear();
+        match asking.reader_type {
+            ReaderType::NewlineBuffered => {
+                log::debug!("Reading a line");
+                let line = backend.read_line(&asking.prompt)?;
+                match &line {
+                    Some(line) => asking.typed = line.trim_end().to_string(),
+                    None => backend.show("\n")?,
+                }
+                Ok(line)
+            }
+            ReaderType::SingleChar => {
+                log::debug!("Reading a single key");
+                let key = match backend.read_key(&asking.prompt) {
+                    Err(err) if err.kind() == io::ErrorKind::Unsupported => {
+                        backend
+                            .message(&fill(self.catalog().keys_unsupported, &[&err.to_string()]));
+                        asking.reader_type = ReaderType::NewlineBuffered;
+                        return self.read_raw(backend, asking);
+                    }
+                    key => key?,
+                };
+                // Keys aren't echoed, so in accessible mode the key is
+                // written out for the screen reader to announce.
+                match key {
+                    Some(ch) if self.accessible && ch.is_ascii_graphic() => {
+                        backend.show(&format!("{}\n", ch))?
+                    }
+                    _ => backend.show("\n")?,
+                }
+                Ok(key.map(String::from))
+            }
+        }
+    }
+
+    fn try_read_value(
+        &self,
+        backend: &mut impl Backend,
+        asking: &mut Asking,
+        attempt: u32,
+    ) -> io::Result<Option<Answer>> {
+        backend.show(&asking.prompt)?;
+        backend.event(Event::PromptShown {
+            prompt: &asking.prompt,
+            attempt,
+        });
+        let input_buf = match self.read_raw(backend, asking)? {
+            Some(buf) => buf,
+            None => return Ok(None),
+        };
+
+        let response = input_buf.trim();
+
+        if response.is_empty() {
+            log::debug!("Empty answer, using the default {:?}", self.default);
+            Ok(Some(self.default))
+        } else if let Some(answer) = self
+            .validator
+            .as_ref()
+            .and_then(|validator| validator.check(response))
+        {
+            log::debug!("The validator took {:?} as {:?}", response, answer);
+            if answer == Answer::Retry {
+                if asking.in_place {
+                    asking.error = Some(self.catalog().not_accepted.to_string());
+                }
+                backend.event(Event::InvalidInput {
+                    input: &text::sanitize(response),
+                });
+            }
+            Ok(Some(answer))
+        } else if self.full_words && self.parse(response) == Answer::Retry {
+            log::debug!("{:?} isn't one of the full-word answers", response);
+            let (yes, no) = self.words();
+            backend.event(Event::InvalidInput {
+                input: &text::sanitize(response),
+            });
+            Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                fill(self.catalog().please_type, &[&yes, no]),
+            ))
+        } else {
+            let answer = self.parse(response);
+            if answer == Answer::Retry {
+                log::debug!("{:?} isn't one of the accepted answers", response);
+                backend.event(Event::InvalidInput {
+                    input: &text::sanitize(response),
+                });
+            }
+            Ok(Some(answer))
+        }
+    }
+
+    /// Ask once, returning the answer, or how asking ended without one.
+    fn get_user_input(
+        &self,
+        backend: &mut impl Backend,
+        asking: &mut Asking,
+        attempt: u32,
+    ) -> Result<Answer, Decision> {
+        match self.try_read_value(backend, asking, attempt) {
+            Ok(Some(answer)) => Ok(answer),
+            Ok(None) => Err(Decision::InputClosed),
+            Err(err) if err.kind() == io::ErrorKind::TimedOut => {
+                Err(Decision::TimedOut(match self.default {
+                    Answer::Retry => Answer::No,
+                    default => default,
+                }))
+            }
+            Err(err) if err.kind() == io::ErrorKind::Interrupted => Err(Decision::Aborted),
+            Err(err) => {
+                if asking.in_place && err.kind() == io::ErrorKind::InvalidInput {
+                    asking.error = Some(err.to_string());
+                } else {
+                    let message = fill(self.catalog().read_error, &[&err.to_string()]);
+                    backend.message(&message);
+                }
+                Ok(Answer::Retry)
+            }
+        }
+    }
+
+    /// Ask until an answer is given, returning how that went; see
+    /// [`Decision::answer`] for what it counts as.
+    ///
+    /// If the backend can redraw, a rejected answer is replaced by the
+    /// prompt again on the same line, with the reason in front of it.
+    pub fn ask(&self, backend: &mut impl Backend) -> Decision {
+        let mut asking = Asking {
+            prompt: self.render_prompt(),
+            reader_type: self.reader_type,
+            typed: String::new(),
+            in_place: backend.can_redraw() && !self.accessible,
+            error: None,
+        };
+
+        let total = self.retry_mode.total_asks();
+        let mut attempt = 1;
+        loop {
+            match self.get_user_input(backend, &mut asking, attempt) {
+                Ok(Answer::Retry) => {}
+                Ok(answer) => {
+                    self.show_outcome(backend, &asking, answer);
+                    backend.event(Event::Answered {
+                        answer: answer.as_str(),
+                    });
+                    return answer.into();
+                }
+                Err(Decision::TimedOut(answer)) => {
+                    self.show_outcome(backend, &asking, answer);
+                    backend.event(Event::TimedOut {
+                        answer: answer.as_str(),
+                    });
+                    return Decision::TimedOut(answer);
+                }
+                Err(Decision::Aborted) => {
+                    backend.event(Event::Aborted { reason: "aborted" });
+                    return Decision::Aborted;
+                }
+                Err(_) => {
+                    backend.message(self.catalog().input_closed);
+                    backend.event(Event::Aborted {
+                        reason: "input-closed",
+                    });
+                    return Decision::InputClosed;
+                }
+            };
+
+            if total == Some(attempt) {
+                break;
+            }
+            attempt += 1;
+            let remaining = total.map(|total| total - attempt + 1);
+            if asking.in_place {
+                let error = asking.error.take().unwrap_or_else(|| {
+                    let (yes, no) = self.words();
+                    fill(self.catalog().please_type, &[&yes, no])
+                });
+                // Nothing is lost if this can't be written, the prompt just
+                // comes on a line of its own.
+                let _ = backend.show(&Self::erase(backend, &asking));
+                asking.prompt = self.render_retry_prompt(remaining, Some(&error));
+            } else if remaining.is_some() {
+                asking.prompt = self.render_retry_prompt(remaining, None);
+            }
+            backend.event(Event::Retry { attempt, remaining });
+        }
+        backend.message(self.catalog().retries_exceeded);
+        backend.event(Event::Aborted {
+            reason: "retries-exhausted",
+        });
+        Decision::RetriesExhausted
+    }
+}
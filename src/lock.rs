@@ -0,0 +1,126 @@
+//! Taking turns on the terminal with other confirm processes.
+//!
+//! With `--lock`, each process holds an exclusive lock on a file while it
+//! asks, so jobs started in parallel ask one after another instead of
+//! interleaving their prompts and reading each other's keystrokes.  The lock
+//! goes away with the process, even if it is killed.
+
+use std::fs::{self, File, OpenOptions, TryLockError};
+use std::path::{Path, PathBuf};
+
+#[cfg(unix)]
+use anyhow::bail;
+use anyhow::{Context, Result};
+
+/// The user's directory for files shared between confirm processes:
+/// `$XDG_RUNTIME_DIR` if there is one, which only the user can write to, or
+/// one named after the user in the temporary directory, which is created
+/// private to them and refused if someone else got there first.
+fn user_dir() -> Result<PathBuf> {
+    if let Some(dir) = std::env::var_os("XDG_RUNTIME_DIR").filter(|dir| !dir.is_empty()) {
+        return Ok(PathBuf::from(dir));
+    }
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::{DirBuilderExt, MetadataExt};
+
+        // SAFETY: getuid has no preconditions and always succeeds.
+        let uid = unsafe { libc::getuid() };
+        let dir = std::env::temp_dir().join(format!("confirm-{}", uid));
+        match fs::DirBuilder::new().mode(0o700).create(&dir) {
+            Err(err) if err.kind() != std::io::ErrorKind::AlreadyExists => {
+                return Err(err).with_context(|| format!("Cannot create {}", dir.display()))
+            }
+            _ => {}
+        }
+        let meta = fs::symlink_metadata(&dir)
+            .with_context(|| format!("Cannot look at {}", dir.display()))?;
+        if !meta.is_dir() || meta.uid() != uid || meta.mode() & 0o077 != 0 {
+            bail!(
+                "{} isn't a directory private to this user, not using it",
+                dir.display()
+            );
+        }
+        Ok(dir)
+    }
+    #[cfg(not(unix))]
+    {
+        let user = std::env::var("USERNAME").unwrap_or_default();
+        let dir = std::env::temp_dir().join(format!("confirm-{}", user));
+        fs::create_dir_all(&dir).with_context(|| format!("Cannot create {}", dir.display()))?;
+        Ok(dir)
+    }
+}
+
+/// A file of the user's, named "confirm" followed by `suffix`, in
+/// [`user_dir`].  Open it with [`take_private`].
+pub fn user_path(suffix: &str) -> Result<PathBuf> {
+    Ok(user_dir()?.join(format!("confirm{}", suffix)))
+}
+
+/// Open the file at `path` for reading and writing, creating it if needed.
+/// With `private`, it's created readable only by the user, never through a
+/// symlink, and refused unless the user owns it and nobody else can write
+/// to it, since what's in it is trusted.
+fn open(path: &Path, private: bool) -> Result<File> {
+    let mut options = OpenOptions::new();
+    options.create(true).read(true).truncate(false).write(true);
+    #[cfg(unix)]
+    if private {
+        use std::os::unix::fs::OpenOptionsExt;
+        options
+            .mode(0o600)
+            .custom_flags(libc::O_NOFOLLOW | libc::O_CLOEXEC);
+    }
+    let file = options
+        .open(path)
+        .with_context(|| format!("Cannot open the lock file {}", path.display()))?;
+    #[cfg(unix)]
+    if private {
+        use std::os::unix::fs::MetadataExt;
+
+        let meta = file
+            .metadata()
+            .with_context(|| format!("Cannot look at {}", path.display()))?;
+        // SAFETY: getuid has no preconditions and always succeeds.
+        if meta.uid() != unsafe { libc::getuid() } || meta.mode() & 0o022 != 0 {
+            bail!(
+                "{} belongs to someone else or others can write to it, not using it",
+                path.display()
+            );
+        }
+    }
+    #[cfg(not(unix))]
+    let _ = private;
+    Ok(file)
+}
+
+/// Wait for the lock at `path`, or the default one, and hold it until the
+/// returned file is dropped.
+pub fn take(path: Option<&Path>) -> Result<File> {
+    match path {
+        Some(path) => hold(open(path, false)?, path),
+        None => take_private(&user_path(".lock")?),
+    }
+}
+
+/// Like [`take`], for a file from [`user_path`] whose content is trusted.
+pub fn take_private(path: &Path) -> Result<File> {
+    hold(open(path, true)?, path)
+}
+
+fn hold(file: File, path: &Path) -> Result<File> {
+    match file.try_lock() {
+        Ok(()) => {}
+        Err(TryLockError::WouldBlock) => {
+            log::debug!("Waiting for another prompt holding {}", path.display());
+            file.lock()
+                .with_context(|| format!("Cannot lock {}", path.display()))?;
+        }
+        Err(TryLockError::Error(err)) => {
+            return Err(err).with_context(|| format!("Cannot lock {}", path.display()))
+        }
+    }
+    log::debug!("Holding the lock on {}", path.display());
+    Ok(file)
+}
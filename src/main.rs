@@ -1,79 +1,170 @@
-use std::convert::Infallible;
-use std::num::NonZeroU8;
-use std::io::{stdin, stdout, Write};
+#[cfg(unix)]
+use std::io::BufReader;
+use std::io::{self, Write};
+#[cfg(unix)]
+use std::os::fd::AsRawFd;
+use std::path::PathBuf;
 use std::str::FromStr;
+#[cfg(unix)]
+use std::time::Duration;
+use std::time::Instant;
 
-use anyhow::{anyhow, Result};
-use clap::Parser;
+use anyhow::{anyhow, bail, Result};
+use clap::{Parser, Subcommand};
+use confirm_rs::events::{Event, EventStream};
+#[cfg(unix)]
+use confirm_rs::i18n::fill;
+use confirm_rs::i18n::{Catalog, Lang};
+use confirm_rs::{
+    degrade_to_ascii, dumb_terminal, locale_is_ascii_only, no_color, Answer, Backend, Decision,
+    Highlight, Icons, Question, ReaderType, TryMode,
+};
 
-#[derive(Debug, Copy, Clone, Eq, PartialEq)]
-enum Answer {
-    Yes,
-    No,
-    Retry,
-}
-
-#[derive(Debug, Copy, Clone, Eq, PartialEq)]
-enum ReaderType {
-    SingleChar,
-    NewlineBuffered,
-}
-
-#[derive(Debug, Copy, Clone, Eq, PartialEq)]
-enum TryMode {
-    Infinite,
-    Count(NonZeroU8),
-}
+mod attention;
+mod audit;
+mod choose;
+mod config;
+#[cfg(all(feature = "desktop", unix, not(target_os = "macos")))]
+mod desktop;
+mod diagnostics;
+mod doctor;
+#[cfg(unix)]
+mod editor;
+mod environment;
+#[cfg(unix)]
+mod fifo;
+#[cfg(unix)]
+mod filter;
+#[cfg(unix)]
+mod gate;
+mod group;
+mod history;
+mod hook;
+mod lock;
+#[cfg(unix)]
+mod picker;
+mod precheck;
+mod preview;
+#[cfg(feature = "script")]
+mod script;
+#[cfg(feature = "self-update")]
+mod self_update;
+#[cfg(unix)]
+mod selftest;
+mod shell;
+#[cfg(unix)]
+mod signal;
+#[cfg(feature = "sound")]
+mod sound;
+mod status;
+#[cfg(target_os = "linux")]
+mod systemd;
+mod term;
+mod validator;
+mod wizard;
 
-impl FromStr for Answer {
-    type Err = Infallible;
+#[cfg(unix)]
+use fifo::Fifo;
+use status::StatusFile;
+use term::{AnswerSource, PromptSink, Terminal};
 
-    fn from_str(s: &str) -> Result<Self, Self::Err> {
-        let low = s.to_ascii_lowercase();
-        match low.as_str() {
-            "yes" | "y" => Ok(Answer::Yes),
-            "no" | "n" => Ok(Answer::No),
-            _ => Ok(Answer::Retry),
-        }
-    }
+/// How prompts are presented.
+#[derive(Debug, Clone, Default)]
+struct Render {
+    /// Complete plain-text lines with the accepted answers spelled out, no
+    /// redrawing and no color-only cues.
+    accessible: bool,
+    /// Only ever print ASCII, for terminals that can't show anything else.
+    ascii: bool,
+    lang: Lang,
+    highlight: Highlight,
+    icons: Option<Icons>,
+    /// Keep diagnostics off stderr.
+    quiet: bool,
+    /// Play a sound through the system audio when asking.
+    #[cfg(feature = "sound")]
+    sound: bool,
+    /// Have the terminal raise a notification and mark the window when
+    /// asking.
+    attention: bool,
+    /// Ring the bell and show the prompt again after this long without an
+    /// answer.
+    #[cfg(unix)]
+    renotify: Option<Duration>,
+    /// Give the default answer after this long without one.
+    #[cfg(unix)]
+    timeout: Option<Duration>,
+    #[cfg(unix)]
+    timeout_mode: TimeoutMode,
+    /// How long a yes can still be taken back with any key.
+    #[cfg(unix)]
+    grace: Option<Duration>,
+    /// Where to describe the pending question.
+    status_file: Option<PathBuf>,
+    /// A command whose output is shown above the question.
+    preview: Option<String>,
+    /// Cut the preview to leave room for the question on the terminal.
+    preview_fit: bool,
+    /// Show a preview longer than the terminal through the pager.
+    pager: bool,
 }
 
-fn is_full_word(s: &str) -> bool {
-    ["yes", "no"].contains(&s.to_ascii_lowercase().as_str())
+/// Whether typing stops the `--timeout` countdown.
+#[derive(Debug, Copy, Clone, Default, Eq, PartialEq, clap::ValueEnum)]
+enum TimeoutMode {
+    /// Stop counting down at the first key, so nobody is answered for
+    /// mid-word
+    #[default]
+    Pause,
+    /// Answer at the deadline no matter what, discarding a partly typed
+    /// answer
+    Strict,
 }
 
-fn parse_default_answer_opt(s: &str) -> Result<Answer> {
-    if !is_full_word(s) && s != "retry" {
-        Err(anyhow!(format!(
-            "Invalid choice, choose either yes or no, found: {}",
-            s
-        )))
-    } else {
-        Ok(Answer::from_str(s)?)
+/// Describe how long we've been waiting, in whole minutes once past the
+/// first one.
+#[cfg(unix)]
+fn format_wait(waited: Duration) -> String {
+    let secs = waited.as_secs();
+    match (secs / 3600, secs / 60 % 60) {
+        (0, 0) => format!("{}s", secs),
+        (0, mins) => format!("{}m", mins),
+        (hours, 0) => format!("{}h", hours),
+        (hours, mins) => format!("{}h {}m", hours, mins),
     }
 }
 
-fn parse_retry_count_opt(s: &str) -> Result<TryMode> {
-    use TryMode::*;
+impl Render {
+    fn catalog(&self) -> &'static Catalog {
+        self.lang.catalog()
+    }
 
-    let count: u8 = s.parse()?;
-    if let Some(nz) = NonZeroU8::new(count) {
-        Ok(Count(nz))
-    } else {
-        Ok(Infinite)
+    /// Text as it should be shown on the terminal.
+    fn display(&self, text: &str) -> String {
+        match self.ascii {
+            true => degrade_to_ascii(text),
+            false => text.to_string(),
+        }
     }
 }
 
 /// Get user confirmation
 #[derive(Clone, Debug, Parser)]
-#[clap(version, about, author, long_about = None)]
+#[command(version, about, author, long_about = None)]
 struct MainOptions {
     /// Require explicit "yes" or "no", not single letters.
     ///
     /// Cannot be used with --no-enter.
-    #[clap(short, long, name = "FULL-WORDS")]
+    #[arg(short, long)]
     full_words: bool,
 
+    /// Also accept 1/0, true/false and on/off as answers
+    ///
+    /// For answers pasted from configuration files or given by tools that
+    /// speak in booleans.  Cannot be used with --danger.
+    #[arg(long, conflicts_with = "danger")]
+    boolean_aliases: bool,
+
     /// Choose a default answer
     ///
     /// If no default is chosen, and the user supplies an empty answer, then a
@@ -81,195 +172,1432 @@ struct MainOptions {
     /// If the retry count has been hit, then the process assumes a negative
     /// response and exits 1. Using the keyword "retry" is identical to
     /// omitting the option.
-    #[clap(short, long, default_value = "retry", parse(try_from_str = parse_default_answer_opt))]
+    #[arg(short, long, value_enum, default_value_t = Answer::Retry)]
     default: Answer,
 
+    /// Pick the default answer by running a command first
+    ///
+    /// The command runs through the shell before asking.  If it succeeds,
+    /// "yes" is the default, otherwise "no", e.g. --check "make test" before
+    /// asking to deploy.  Its output isn't shown unless --check-output is
+    /// given.  Can't be set in the configuration files.
+    #[arg(long, value_name = "COMMAND", conflicts_with_all = ["default", "danger"])]
+    check: Option<String>,
+
+    /// Show the first line the --check command prints after the prompt
+    #[arg(long, requires = "check")]
+    check_output: bool,
+
+    /// Only ask if this command succeeds, otherwise answer yes
+    ///
+    /// The command runs through the shell, with its output discarded.  E.g.
+    /// --if "test -d build" asks before deleting the build directory only if
+    /// there is one to delete.  Can't be set in the configuration files.
+    #[arg(long = "if", value_name = "COMMAND")]
+    if_command: Option<String>,
+
+    /// Only ask if this command fails, otherwise answer yes
+    ///
+    /// Like --if with the result reversed.  Given both, the question is
+    /// asked only if --if succeeds and --unless fails.
+    #[arg(long = "unless", value_name = "COMMAND")]
+    unless_command: Option<String>,
+
+    /// Show what this command prints above the question
+    ///
+    /// The command runs through the shell right before asking, so the
+    /// question comes with what it's about, e.g. --preview "terraform plan"
+    /// or --preview "git diff --stat".  Escape sequences in the output are
+    /// removed.  Can't be set in the configuration files.
+    #[arg(long, value_name = "COMMAND")]
+    preview: Option<String>,
+
+    /// Cut the --preview output to fit on the terminal with the question
+    #[arg(long, requires = "preview")]
+    preview_fit: bool,
+
+    /// Show --preview output too long for the terminal through $PAGER
+    ///
+    /// The pager, less unless $PAGER says otherwise, shows the whole output
+    /// before the question is asked below it.  Output that fits on the
+    /// screen is shown as usual.
+    #[arg(long, requires = "preview", conflicts_with = "preview_fit")]
+    pager: bool,
+
+    /// Decide what each answer means by running a command
+    ///
+    /// The command runs through the shell with the answer on stdin and in
+    /// $CONFIRM_INPUT.  If it prints "yes", "no" or "retry", that's the
+    /// answer, otherwise exiting 0 means yes, 1 no and anything else asks
+    /// again, e.g. --validator 'grep -qE "^[A-Z]+-[0-9]+$" || exit 2' to accept a
+    /// ticket number.  Empty answers still get the default.  Can't be set
+    /// in the configuration files.
+    #[arg(long, value_name = "COMMAND", conflicts_with = "danger")]
+    validator: Option<String>,
+
+    /// Customize the question with a Rhai script
+    ///
+    /// The script can define prompt(text) to change the prompt,
+    /// classify(input) to decide what answers mean, returning "yes", "no",
+    /// "neutral", "retry" or nothing for the usual words, and tries() for
+    /// how many times to ask.  Can't be set in the configuration files.
+    #[cfg(feature = "script")]
+    #[arg(long, value_name = "FILE", conflicts_with_all = ["danger", "validator"])]
+    script: Option<PathBuf>,
+
+    /// What the script's classify() makes of answers.
+    #[cfg(feature = "script")]
+    #[arg(skip)]
+    script_validator: Option<confirm_rs::Validator>,
+
     /// Don't require newlines
     ///
     /// Read the character on the terminal as it's typed, without waiting for
-    /// the user to hit enter/return.  
-    #[clap(long, conflicts_with = "FULL-WORDS")]
+    /// the user to hit enter/return.
+    #[arg(long, conflicts_with = "full_words")]
     no_enter: bool,
 
     /// Number of times to ask
     ///
     /// Number of total times a question should be asked.  Use 0 for infinite
     /// retries.
-    #[clap(short, long, default_value = "3", parse(try_from_str = parse_retry_count_opt))]
-    ask_count: TryMode,
+    #[arg(short, long, default_value_t = 3)]
+    ask_count: u8,
+
+    /// How the remaining attempts are shown when asking again
+    ///
+    /// With a finite --ask-count, a prompt that's asked again shows e.g.
+    /// "(2 tries left)".  {} stands for the number of attempts left, and an
+    /// empty format hides it.
+    #[arg(long, value_name = "FORMAT")]
+    tries_format: Option<String>,
 
     /// The prompt to display
     ///
     /// Prompt of "Continue?" will become "Continue? [y/n]: ".  Options are
     /// added and highlighted based on given settings.  Original message will
-    /// NEVER be modified.
-    #[clap(name = "PROMPT", default_value = "Continue?")]
+    /// NEVER be modified, except for replacing non-ASCII characters in ASCII
+    /// mode.
+    #[arg(default_value = "Continue?")]
     prompt: String,
 
     /// Don't ask any question, return successfully.
-    /// 
+    ///
     /// Turns the entire tool into a no-op, useful when building shell scripts
-    /// around the tool.
-    #[clap(long = "--yes")]
+    /// around the tool.  `gate` passes its input on, `filter` passes every
+    /// item, and `choose` and the wizard's choose and input steps take their
+    /// defaults, failing without one; the wizard's confirm steps are yes.
+    #[arg(long = "yes", conflicts_with = "always_no")]
     always_yes: bool,
 
     /// Don't ask any question, fail immediately.
-    /// 
+    ///
     /// Turns the tool into no-op failure.  Useful when testing shell scripts
-    /// built around this tool.
-    #[clap(long = "--no")]
+    /// built around this tool.  `gate` and `filter` pass nothing on, `choose`
+    /// and the wizard's choose and input steps take their defaults, failing
+    /// without one, and the wizard's confirm steps are no.
+    #[arg(long = "no")]
     always_no: bool,
+
+    /// Don't print diagnostics
+    ///
+    /// Leaves only the prompt and the exit code: no warning when stdin isn't
+    /// a terminal, no explanation of rejected answers, and no message when
+    /// giving up.  For scripts that present their own messaging around the
+    /// tool.
+    #[arg(short, long)]
+    quiet: bool,
+
+    /// Explain what's happening on stderr
+    ///
+    /// Shows where answers are read from, which configuration files are
+    /// loaded and why an answer is asked again.  Given twice, also shows the
+    /// raw input as it's received, for debugging odd terminal behavior.
+    #[arg(short, long, action = clap::ArgAction::Count, conflicts_with = "quiet")]
+    verbose: u8,
+
+    /// Accept a third answer that is neither yes nor no
+    ///
+    /// E.g. --neutral later lets the user defer the decision, which exits
+    /// with --neutral-code instead of 0 or 1.  Must be a single character with
+    /// --no-enter.
+    #[arg(long, value_name = "ANSWER")]
+    neutral: Option<String>,
+
+    /// Exit code for the neutral answer
+    #[arg(
+        long,
+        value_name = "CODE",
+        default_value_t = DEFAULT_NEUTRAL_CODE,
+        requires = "neutral",
+        value_parser = clap::value_parser!(u8).range(2..)
+    )]
+    neutral_code: u8,
+
+    /// Mark the question as destructive
+    ///
+    /// Shows the prompt in bold red, requires "yes" or "no" spelled out,
+    /// makes "no" the default and asks only once, so a stray keypress can
+    /// never confirm.  Cannot be combined with options that would weaken
+    /// this, like --default or --no-enter.
+    #[arg(long, conflicts_with_all = ["no_enter", "default", "ask_count", "full_words"])]
+    danger: bool,
+
+    /// With --danger, require typing this text instead of "yes"
+    ///
+    /// E.g. --require-match prod-db makes the user type the name of the
+    /// database about to be dropped.  It must be typed exactly, case
+    /// included.
+    #[arg(long, value_name = "TEXT", requires = "danger")]
+    require_match: Option<String>,
+
+    /// Read exactly one answer from piped stdin
+    ///
+    /// For automation like `echo yes | confirm --stdin-answer "Proceed?"`:
+    /// there's no warning about stdin not being a terminal and no retries.
+    /// The first line is the answer, with an empty line picking the default,
+    /// and anything that isn't an accepted answer fails.
+    #[arg(long, conflicts_with = "no_enter")]
+    stdin_answer: bool,
+
+    /// Fail unless answers come from a terminal
+    ///
+    /// Instead of warning when stdin (or --answer-fd, or --tty) isn't a
+    /// terminal, exit immediately with code 4, so safety-critical scripts
+    /// never proceed when the prompt can't reach a person.
+    #[arg(long, conflicts_with = "stdin_answer")]
+    require_tty: bool,
+
+    /// Wait for other prompts using the same lock to be answered first
+    ///
+    /// Holds a lock on the file at PATH while asking, by default one per
+    /// user, so scripts running several confirms in parallel ask one at a
+    /// time instead of mixing up their prompts and answers on the same
+    /// terminal.  The path has to be given with an equals sign, e.g.
+    /// --lock=/tmp/deploy.lock.
+    #[arg(long, value_name = "PATH", num_args = 0..=1, require_equals = true)]
+    lock: Option<Option<PathBuf>>,
+
+    /// Share the answer with other invocations using the same key
+    ///
+    /// The first one asks, and the others give the same answer without
+    /// asking, waiting for it if the question is still open.  For answering
+    /// once per batch, e.g. under xargs or parallel.
+    #[arg(long, value_name = "KEY")]
+    group: Option<String>,
+
+    /// How many seconds a --group answer is reused after the group was last
+    /// asked
+    #[arg(long, value_name = "SECONDS", default_value_t = 60, requires = "group")]
+    group_window: u32,
+
+    /// Read answers from this file descriptor instead of stdin
+    ///
+    /// The prompt is still displayed on stdout, so a supervising process can
+    /// answer over an inherited pipe while the question remains visible on
+    /// the terminal.  The tool aborts if the descriptor is closed before an
+    /// answer is read.
+    #[cfg(unix)]
+    #[arg(
+        long,
+        value_name = "FD",
+        value_parser = clap::value_parser!(i32).range(0..),
+        conflicts_with = "stdin_answer"
+    )]
+    answer_fd: Option<i32>,
+
+    /// Prompt on this terminal device instead of stdin/stdout
+    ///
+    /// Both the prompt and the answer use the given device, e.g. /dev/tty,
+    /// /dev/pts/3 or a serial console, so confirmations from daemons land on
+    /// the operator's console.  When combined with --answer-fd, only the
+    /// prompt is shown on the device.
+    #[cfg(unix)]
+    #[arg(long, value_name = "PATH", conflicts_with = "stdin_answer")]
+    tty: Option<PathBuf>,
+
+    /// Screen-reader friendly output
+    ///
+    /// Print the prompt as a complete line that spells out the accepted
+    /// answers and the default, and never redraw text in place or rely on
+    /// color alone to convey meaning.
+    #[arg(long)]
+    accessible: bool,
+
+    /// Only print ASCII characters
+    ///
+    /// Replace typographic symbols in the output (including the prompt) with
+    /// plain ASCII for serial consoles, old terminals and minimal containers.
+    /// Turned on automatically when the locale uses a character set other
+    /// than UTF-8.
+    #[arg(long)]
+    ascii: bool,
+
+    /// Language of the hint, the accepted answers and the messages
+    ///
+    /// One of en, de, fr or es, e.g. "de" shows "[j/N]" and accepts "ja" or
+    /// "j".  Locale names like de_DE.UTF-8 work too.  Defaults to the
+    /// language of the locale (LC_ALL, LC_MESSAGES or LANG), or English.
+    /// English answers are always accepted.
+    #[arg(long, value_name = "LANG")]
+    lang: Option<Lang>,
+
+    /// How the default answer stands out in the hint
+    ///
+    /// Uppercase can be hard to spot in some fonts and with non-English
+    /// answers.  The color style falls back to uppercase when NO_COLOR is set.
+    #[arg(long, value_enum, value_name = "STYLE", default_value_t = Highlight::Uppercase)]
+    highlight: Highlight,
+
+    /// Show symbols in front of the prompt
+    ///
+    /// "?" while asking, then the prompt line is rewritten with "✔" and the
+    /// answer for yes or "✖" for no.  Other symbols can be given as three
+    /// comma-separated values, e.g. --icons='?,+,-'.  Only rewritten on a
+    /// terminal, and not used with --accessible.
+    #[arg(
+        long,
+        value_name = "ASK,YES,NO",
+        num_args = 0..=1,
+        require_equals = true,
+        default_missing_value = "?,\u{2714},\u{2716}"
+    )]
+    icons: Option<Icons>,
+
+    /// Play a sound when the question is asked, and when it times out
+    ///
+    /// The cues are played through the system's audio player, for operators
+    /// watching long-running jobs from across the room.
+    #[cfg(feature = "sound")]
+    #[arg(long)]
+    sound: bool,
+
+    /// Have the terminal draw attention to the question
+    ///
+    /// Sends the escape sequences terminals use for notifications, along
+    /// with a bell, so the tab or window with the question gets marked even
+    /// when it's in the background, including inside tmux and screen.  Only
+    /// sent when the prompt goes to a terminal.
+    #[arg(long)]
+    attention: bool,
+
+    /// Remind the user after this many minutes without an answer
+    ///
+    /// Rings the terminal bell (and plays the sound cue, if enabled) and shows
+    /// the prompt again with how long it has been waiting, repeating at the
+    /// same interval, so a returning user immediately sees what is blocked.
+    /// Only applies when answers come from a terminal.
+    #[cfg(unix)]
+    #[arg(long, value_name = "MINUTES", value_parser = clap::value_parser!(u32).range(1..))]
+    renotify: Option<u32>,
+
+    /// Give the default answer after this many seconds without one
+    ///
+    /// Needs --default yes or no (or --danger, which answers no).  By
+    /// default the countdown stops as soon as a key is typed on a terminal,
+    /// see --timeout-mode.
+    #[cfg(unix)]
+    #[arg(long, value_name = "SECONDS", value_parser = clap::value_parser!(u32).range(1..))]
+    timeout: Option<u32>,
+
+    /// Whether typing stops the --timeout countdown
+    #[cfg(unix)]
+    #[arg(
+        long,
+        value_enum,
+        value_name = "MODE",
+        default_value_t = TimeoutMode::Pause,
+        requires = "timeout"
+    )]
+    timeout_mode: TimeoutMode,
+
+    /// After a yes, wait this many seconds for a key that takes it back
+    ///
+    /// Shows "Proceeding in 5s, press any key to cancel" and counts down,
+    /// exiting 1 instead of 0 if any key is pressed in time, as a last
+    /// chance to stop a destructive command.  Only applies when answers come
+    /// from a terminal.
+    #[cfg(unix)]
+    #[arg(long, value_name = "SECONDS", value_parser = clap::value_parser!(u32).range(1..))]
+    grace: Option<u32>,
+
+    /// Report progress as JSON events on stdout
+    ///
+    /// Writes one JSON object per line for each step: "prompt-shown",
+    /// "invalid-input", "retry", and finally "answered", "timed-out" (with
+    /// the default taken as the answer) or "aborted".  The prompt itself
+    /// moves to stderr, unless --tty is used.
+    #[arg(long)]
+    machine: bool,
+
+    /// Print the answer as a shell assignment on stdout
+    ///
+    /// Prints CONFIRM_ANSWER=yes or CONFIRM_ANSWER=no, suitable for
+    /// `eval "$(confirm --shell-output ...)"`.  The wizard prints one
+    /// CONFIRM_ANSWER_<ID> variable per question instead.  Both also set
+    /// CONFIRM_REPORTED, listing what they set, so that exported answers
+    /// don't answer later questions the way seeded ones do, see --id.  The
+    /// prompt itself moves to stderr, unless --tty is used.
+    #[arg(long, conflicts_with = "machine")]
+    shell_output: bool,
+
+    /// Print the prompt as it would be shown and exit, without asking
+    ///
+    /// The prompt line is composed from the other options, with the hint,
+    /// highlighting, icons and language, and printed on stdout followed by a
+    /// newline.  Nothing is read and the exit code is 0, for previewing
+    /// prompts and comparing them in tests.
+    #[arg(long, conflicts_with_all = ["machine", "shell_output"])]
+    render_only: bool,
+
+    /// Also take answers written to a named pipe at this path
+    ///
+    /// The pipe is created while asking and removed afterwards, and a line
+    /// written to it, e.g. with `echo yes > PATH` from another shell,
+    /// answers as if it had been typed.  Anything but a FIFO of the
+    /// user's left by an earlier run must not exist there yet.
+    #[cfg(unix)]
+    #[arg(long, value_name = "PATH")]
+    fifo: Option<PathBuf>,
+
+    /// Name the question, for telling answers apart in the audit log and
+    /// answering it ahead of time with CONFIRM_ANSWER_<ID>
+    ///
+    /// CONFIRM_ANSWER_<ID> set to yes or no answers the question with this
+    /// id, and CONFIRM_ANSWER any question, without asking.  Values printed
+    /// by --shell-output and listed in CONFIRM_REPORTED are not used this
+    /// way, so evaluating and exporting them doesn't answer later questions.
+    #[arg(long)]
+    id: Option<String>,
+
+    /// Log every answer to a file
+    ///
+    /// Appends a line of JSON with the time, the --id, the prompt, the
+    /// answer, the response time and the user to
+    /// ~/.local/state/confirm/audit.jsonl, or to PATH, for `confirm history`
+    /// to review.  The path has to be given with an equals sign.
+    #[arg(long, value_name = "PATH", num_args = 0..=1, require_equals = true)]
+    audit_log: Option<Option<PathBuf>>,
+
+    /// Describe the question in this file while it's waiting for an answer
+    ///
+    /// The file holds a JSON object with the prompt, the pid, when asking
+    /// started (in seconds since 1970) and the number of attempts so far,
+    /// for dashboards and status bars.  It is removed once answered.
+    #[arg(long, value_name = "PATH")]
+    status_file: Option<PathBuf>,
+
+    /// Signal this process with the answer
+    ///
+    /// Sends --signal on yes and --signal-no on no, for daemons that wait
+    /// for a signal instead of the exit code.  The exit code is the same as
+    /// usual.
+    #[cfg(unix)]
+    #[arg(long, value_name = "PID", value_parser = clap::value_parser!(u32).range(1..))]
+    signal_pid: Option<u32>,
+
+    /// The signal sent to --signal-pid on yes
+    #[cfg(unix)]
+    #[arg(
+        long,
+        value_name = "SIG",
+        default_value = "USR1",
+        requires = "signal_pid"
+    )]
+    signal: signal::Signal,
+
+    /// The signal sent to --signal-pid on no, none by default
+    #[cfg(unix)]
+    #[arg(long, value_name = "SIG", requires = "signal_pid")]
+    signal_no: Option<signal::Signal>,
+
+    /// Read the question as a JSON object on stdin
+    ///
+    /// E.g. {"prompt": "Deploy?", "default": "no", "timeout": 30}, with the
+    /// same keys as the configuration files.  The prompt is shown on
+    /// /dev/tty (or --tty) and the answer is written to stdout as
+    /// {"answer": "yes"}, for programs that would rather not build a command
+    /// line.  The exit code is the same as usual.
+    #[cfg(unix)]
+    #[arg(long, conflicts_with_all = ["stdin_answer", "machine", "shell_output"])]
+    json_request: bool,
+
+    /// Ask with a desktop notification instead of on the terminal
+    ///
+    /// The question is shown as a notification with Yes and No buttons, for
+    /// scripts launched from a graphical session with no terminal attached.
+    /// Clicking the notification itself picks the default answer, dismissing
+    /// it counts as "no".
+    #[cfg(all(feature = "desktop", unix, not(target_os = "macos")))]
+    #[arg(
+        long,
+        conflicts_with_all = ["no_enter", "answer_fd", "tty", "stdin_answer", "require_tty", "danger", "timeout", "grace", "fifo", "validator", "preview"]
+    )]
+    desktop: bool,
+
+    /// Ignore the configuration files
+    ///
+    /// Options are otherwise read from ~/.config/confirm/config.toml and
+    /// from the nearest .confirm.toml in the working directory or its
+    /// parents, with keys named after the long options.
+    #[arg(long)]
+    no_config: bool,
+
+    /// Apply a named bundle of options from the configuration files
+    ///
+    /// Profiles are defined as [profile.<NAME>] tables, e.g. a "dangerous"
+    /// profile with full-words = true and ask-count = 1.  Options on the
+    /// command line still take precedence.
+    #[arg(long, value_name = "NAME", conflicts_with = "no_config")]
+    profile: Option<String>,
+
+    #[command(subcommand)]
+    command: Option<Command>,
+}
+
+#[derive(Clone, Debug, Subcommand)]
+enum Command {
+    /// Answer pending systemd ask-password questions
+    ///
+    /// Acts as a systemd password agent for questions asked with
+    /// `systemd-ask-password --echo`, displaying each one as a confirmation
+    /// prompt using the options given before the subcommand.  A "yes" is sent
+    /// back as the answer "yes", anything else cancels the question.
+    #[cfg(target_os = "linux")]
+    SystemdAgent(systemd::AgentOptions),
+
+    /// Run a multi-step question flow from a definition file
+    ///
+    /// The file lists yes/no, choose and input steps, optionally conditional
+    /// on earlier answers.  Questions are asked on stderr (or --tty) using the
+    /// options given before the subcommand, and the answers are printed on
+    /// stdout as JSON or as shell assignments.
+    Wizard(wizard::WizardOptions),
+
+    /// Pass stdin on to stdout only if the user approves
+    ///
+    /// Reads all of stdin, shows the first lines of it on /dev/tty (or
+    /// --tty) and asks there, then copies the input to stdout on "yes" and
+    /// writes nothing otherwise, e.g. `generate | confirm gate "Apply?" |
+    /// apply`.
+    #[cfg(unix)]
+    Gate(gate::GateOptions),
+
+    /// Ask about each line of stdin and pass on only the approved ones
+    ///
+    /// Each item is asked about on /dev/tty (or --tty), answering "a" to
+    /// approve it and all the rest or "q" to drop it and all the rest.  An
+    /// empty answer drops the item, unless --default yes is given.  Fails if
+    /// no item was approved.
+    #[cfg(unix)]
+    Filter(filter::FilterOptions),
+
+    /// Pick one of a list of options and print it
+    ///
+    /// The options are listed on stderr (or --tty) and the one picked is
+    /// printed on stdout.  On a terminal, typing filters the list, and the
+    /// arrow keys and enter pick from it.  Given `-`, the
+    /// options are read from stdin and the question is asked on /dev/tty,
+    /// e.g. `git branch --format='%(refname:short)' | confirm choose - |
+    /// xargs git switch`.
+    Choose(choose::ChooseOptions),
+
+    /// Install or remove git hooks that ask before a commit or push
+    ///
+    /// `confirm hook install pre-push` writes a hook to the repository's
+    /// hooks directory that asks "Push main to origin?" on /dev/tty, with
+    /// "no" as the default.  Hooks not written by confirm are left alone.
+    Hook(hook::HookOptions),
+
+    /// Check how the terminal features work on this system
+    ///
+    /// Runs the tool under a pseudo-terminal to try reading lines, single
+    /// keys, the timeout, color and redrawing, and reports which of them
+    /// work.  Attach the report to bug reports about terminal behavior.
+    #[cfg(unix)]
+    Selftest,
+
+    /// Report how confirm sees the terminal, locale and configuration
+    ///
+    /// Lists whether stdin, stdout and stderr are terminals, TERM and
+    /// whether color is used, whether single keys can be read, the locale,
+    /// the config files loaded, and where the options given before the
+    /// subcommand would show the prompt and read the answer from.  Nothing
+    /// is asked.
+    Doctor,
+
+    /// Replace this executable with the latest release
+    ///
+    /// Downloads the release for this platform from GitHub with curl and
+    /// asks before replacing the executable, using the options given before
+    /// the subcommand.  The release's SHA256SUMS must carry a minisign
+    /// signature from the release key built into confirm, and the download
+    /// must match it.  Builds without a key and releases that don't publish
+    /// signed executables are only reported.
+    #[cfg(feature = "self-update")]
+    SelfUpdate(self_update::SelfUpdateOptions),
+
+    /// List the answers in the audit log, with totals
+    ///
+    /// Reads the log written with --audit-log, optionally only the answers
+    /// to one --id, of one kind or from some days, and sums up how many of
+    /// each answer were given and how long answering took on average.
+    History(audit::HistoryOptions),
 }
 
 impl MainOptions {
-    fn into_confirm(self) -> Confirm {
+    /// Wait for the `--lock`, unless answering without asking, and hold it
+    /// until the returned file is dropped.
+    fn take_lock(&self) -> Result<Option<std::fs::File>> {
+        match &self.lock {
+            Some(path) if self.preset().is_none() => Ok(Some(lock::take(path.as_deref())?)),
+            _ => Ok(None),
+        }
+    }
+
+    /// The answer to give without asking, from `--yes` or `--no`, the
+    /// environment or the guards.
+    fn preset(&self) -> Option<Answer> {
+        match (self.always_yes, self.always_no) {
+            (true, _) => Some(Answer::Yes),
+            (_, true) => Some(Answer::No),
+            _ => None,
+        }
+    }
+
+    fn reads_stdin(&self) -> bool {
+        #[cfg(unix)]
+        if self.answer_fd.is_some() || self.tty.is_some() {
+            return false;
+        }
+        true
+    }
+
+    /// Whether answers come from a terminal, where a person can see the
+    /// prompt and answer it.
+    fn answers_from_terminal(&self) -> bool {
+        #[cfg(unix)]
+        {
+            if let Some(fd) = self.answer_fd {
+                return term::is_terminal(fd);
+            }
+            if let Some(path) = &self.tty {
+                return term::open_tty(path).is_ok_and(|tty| term::is_terminal(tty.as_raw_fd()));
+            }
+        }
+        atty::is(atty::Stream::Stdin)
+    }
+
+    fn default_sink(&self) -> PromptSink {
+        match self.machine || self.shell_output {
+            true => PromptSink::Stderr,
+            false => PromptSink::Stdout,
+        }
+    }
+
+    fn events(&self) -> Option<EventStream> {
+        self.machine.then(EventStream::default)
+    }
+
+    fn terminal(&self) -> Result<Terminal> {
+        #[cfg(unix)]
+        {
+            let tty = self.tty.as_deref().map(term::open_tty).transpose()?;
+            let source = match (self.answer_fd, &tty) {
+                (Some(fd), _) => AnswerSource::Fd(BufReader::new(term::open_answer_fd(fd)?)),
+                (None, Some(tty)) => AnswerSource::Tty(BufReader::new(tty.try_clone()?)),
+                (None, None) => AnswerSource::Stdin,
+            };
+            let sink = match tty {
+                Some(tty) => PromptSink::Tty(tty),
+                None => self.default_sink(),
+            };
+            Ok(Terminal {
+                source,
+                sink,
+                events: self.events(),
+                fifo: self.fifo.as_deref().map(Fifo::create).transpose()?,
+            })
+        }
+        #[cfg(not(unix))]
+        Ok(Terminal {
+            source: AnswerSource::Stdin,
+            sink: self.default_sink(),
+            events: self.events(),
+        })
+    }
+
+    fn render(&self) -> Render {
+        Render {
+            accessible: self.accessible,
+            ascii: self.ascii || locale_is_ascii_only(),
+            lang: self.lang.unwrap_or_else(Lang::from_env),
+            highlight: match self.highlight {
+                Highlight::Color if no_color() => Highlight::Uppercase,
+                highlight => highlight,
+            },
+            icons: self.icons.clone(),
+            quiet: self.quiet,
+            #[cfg(feature = "sound")]
+            sound: self.sound,
+            attention: self.attention,
+            #[cfg(unix)]
+            renotify: self
+                .renotify
+                .map(|mins| Duration::from_secs(u64::from(mins) * 60)),
+            #[cfg(unix)]
+            timeout: self
+                .timeout
+                .map(|secs| Duration::from_secs(u64::from(secs))),
+            #[cfg(unix)]
+            timeout_mode: self.timeout_mode,
+            #[cfg(unix)]
+            grace: self.grace.map(|secs| Duration::from_secs(u64::from(secs))),
+            status_file: self.status_file.clone(),
+            preview: self.preview.clone(),
+            preview_fit: self.preview_fit,
+            pager: self.pager,
+        }
+    }
+
+    #[cfg(feature = "script")]
+    fn script_validator(&mut self) -> Option<confirm_rs::Validator> {
+        self.script_validator.take()
+    }
+
+    #[cfg(not(feature = "script"))]
+    fn script_validator(&mut self) -> Option<confirm_rs::Validator> {
+        None
+    }
+
+    fn try_mode(&self) -> TryMode {
+        match self.stdin_answer || self.danger {
+            true => TryMode::Once,
+            false => TryMode::from(self.ask_count),
+        }
+    }
+
+    fn into_confirm(self) -> Result<Confirm> {
+        let terminal = self.terminal()?;
+        Ok(self.confirm_on(terminal))
+    }
+
+    /// Build the prompt these options describe on an already open terminal.
+    fn confirm_on(self, terminal: Terminal) -> Confirm {
+        let render = self.render();
+        Confirm::new(self.into_question(), terminal, render)
+    }
+
+    /// The question these options describe.
+    fn into_question(mut self) -> Question {
         let reader_type = match self.no_enter {
             true => ReaderType::SingleChar,
             false => ReaderType::NewlineBuffered,
         };
-        Confirm::new(
-            self.default,
-            self.prompt,
+        let render = self.render();
+        let retry_mode = self.try_mode();
+        let validator = self.script_validator().or_else(|| {
+            self.validator
+                .take()
+                .map(|command| validator::command(command, render.quiet))
+        });
+        Question {
+            prompt: self.prompt,
+            default: match self.danger {
+                true => Answer::No,
+                false => self.default,
+            },
             reader_type,
-            self.ask_count,
-            self.full_words,
-        )
+            retry_mode,
+            full_words: self.full_words || self.danger,
+            boolean_aliases: self.boolean_aliases,
+            accessible: render.accessible,
+            ascii: render.ascii,
+            lang: render.lang,
+            highlight: render.highlight,
+            icons: render.icons.clone(),
+            neutral: self.neutral,
+            danger: self.danger,
+            require_match: self.require_match,
+            tries_format: self.tries_format,
+            validator,
+        }
+    }
+
+    /// Reject combinations of options that clap can't tell are invalid,
+    /// like those coming from configuration files: every conflict declared
+    /// on an option that can be configured is repeated here.
+    fn check(&self) -> Result<()> {
+        let conflict = |a: &str, b: &str| Err(anyhow!("--{} cannot be used with --{}", a, b));
+        if self.render_only && self.command.is_some() {
+            bail!("--render-only cannot be used with a subcommand");
+        }
+        if self.danger && self.no_enter {
+            return conflict("danger", "no-enter");
+        }
+        if self.full_words && self.no_enter {
+            return conflict("full-words", "no-enter");
+        }
+        if self.danger && self.boolean_aliases {
+            return conflict("danger", "boolean-aliases");
+        }
+        if self.danger && self.check.is_some() {
+            return conflict("check", "danger");
+        }
+        if self.danger && self.validator.is_some() {
+            return conflict("validator", "danger");
+        }
+        #[cfg(feature = "script")]
+        if self.danger && self.script.is_some() {
+            return conflict("script", "danger");
+        }
+        if self.quiet && self.verbose > 0 {
+            return conflict("verbose", "quiet");
+        }
+        if self.stdin_answer && self.no_enter {
+            return conflict("stdin-answer", "no-enter");
+        }
+        if self.stdin_answer && self.require_tty {
+            return conflict("require-tty", "stdin-answer");
+        }
+        #[cfg(all(feature = "desktop", unix, not(target_os = "macos")))]
+        if self.desktop {
+            let configured = [
+                ("no-enter", self.no_enter),
+                ("require-tty", self.require_tty),
+                ("danger", self.danger),
+                ("timeout", self.timeout.is_some()),
+                ("grace", self.grace.is_some()),
+            ];
+            if let Some((name, _)) = configured.iter().find(|(_, set)| *set) {
+                return conflict("desktop", name);
+            }
+        }
+        if self.neutral.is_none() && self.neutral_code != DEFAULT_NEUTRAL_CODE {
+            bail!("--neutral-code requires --neutral");
+        }
+        #[cfg(unix)]
+        if self.timeout.is_none() && self.timeout_mode != TimeoutMode::default() {
+            bail!("--timeout-mode requires --timeout");
+        }
+        #[cfg(unix)]
+        if self.timeout.is_some() && self.default == Answer::Retry && !self.danger {
+            bail!("--timeout needs --default yes or no to answer with");
+        }
+        if let Some(text) = &self.require_match {
+            let catalog = self.render().catalog();
+            if text.trim().is_empty() || [catalog.no, "no"].contains(&text.to_lowercase().as_str())
+            {
+                bail!("The text to match {:?} must differ from no", text);
+            }
+        }
+        if let Some(neutral) = &self.neutral {
+            let catalog = self.render().catalog();
+            let taken = [catalog.yes, catalog.no, catalog.y, catalog.n];
+            let low = neutral.to_lowercase();
+            if low.trim().is_empty()
+                || taken.contains(&low.as_str())
+                || Answer::from_str(&low).is_ok_and(|answer| answer != Answer::Retry)
+            {
+                bail!(
+                    "The neutral answer {:?} must differ from yes and no",
+                    neutral
+                );
+            }
+            if self.no_enter && neutral.chars().count() != 1 {
+                bail!("The neutral answer must be a single character with --no-enter");
+            }
+        }
+        Ok(())
     }
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug)]
 struct Confirm {
-    default_response: Answer,
-    prompt: String,
-    reader_type: ReaderType,
-    retry_mode: TryMode,
-    use_full_words: bool,
+    question: Question,
+    console: Console,
 }
 
 impl Confirm {
-    pub fn new(
-        default_response: Answer,
-        prompt: String,
-        reader_type: ReaderType,
-        retry_mode: TryMode,
-        use_full_words: bool,
-    ) -> Self {
+    pub fn new(question: Question, terminal: Terminal, render: Render) -> Self {
+        log::debug!(
+            "Answers from {}, prompt on {}",
+            terminal.source,
+            terminal.sink
+        );
+        let status = render
+            .status_file
+            .clone()
+            .map(|path| StatusFile::new(path, &question.prompt));
         Self {
-            default_response,
-            reader_type,
-            prompt,
-            use_full_words,
-            retry_mode,
+            question,
+            console: Console {
+                answer_source: terminal.source,
+                prompt_sink: terminal.sink,
+                events: terminal.events,
+                #[cfg(unix)]
+                fifo: terminal.fifo,
+                status,
+                render,
+            },
         }
     }
 
-    fn render_option_box(&self) -> &'static str {
-        use Answer::*;
-        match (self.use_full_words, self.default_response) {
-            (true, Yes) => "[YES/no]",
-            (true, No) => "[yes/NO]",
-            (true, Retry) => "[yes/no]",
-            (false, Yes) => "[Y/n]",
-            (false, No) => "[y/N]",
-            (false, Retry) => "[y/n]",
+    /// Hand the terminal back, e.g. to ask a follow-up question on it.
+    fn into_terminal(self) -> Terminal {
+        Terminal {
+            source: self.console.answer_source,
+            sink: self.console.prompt_sink,
+            events: self.console.events,
+            #[cfg(unix)]
+            fifo: self.console.fifo,
         }
     }
 
-    fn prepare_prompt(&self) -> String {
-        let optionbox = self.render_option_box();
-        let mut new = self.prompt.clone();
-        new.push(' ');
-        new.push_str(optionbox);
-        new.push_str(": ");
-        new
+    pub fn ask_loop(&mut self) -> Decision {
+        self.console.show_preview();
+        self.console.play_cue();
+        self.console.draw_attention(&self.question.prompt);
+        let decision = self.question.ask(&mut self.console);
+        log::debug!("Asking ended with {:?}", decision);
+        #[cfg(unix)]
+        if decision.answer() == Answer::Yes {
+            return match self.console.hold_back() {
+                Answer::Yes => decision,
+                _ => Decision::Aborted,
+            };
+        }
+        decision
     }
+}
 
-    fn try_read_value(&self, prompt: &str) -> Result<Answer> {
-        use ReaderType::*;
-        print!("{}", prompt);
-        stdout().flush()?;
-        let mut input_buf = String::new();
-        match self.reader_type {
-            NewlineBuffered => {
-                stdin().read_line(&mut input_buf)?;
+/// What [`Console::wait_for_answer`] stopped waiting for.
+enum Waited {
+    /// There's an answer to read.
+    Input,
+    /// A line came through the `--fifo` pipe instead.
+    #[cfg(unix)]
+    Fifo(String),
+    /// The timeout passed first.
+    #[cfg_attr(not(unix), allow(dead_code))]
+    TimedOut,
+}
+
+/// The terminal a question is asked on.
+#[derive(Debug)]
+struct Console {
+    answer_source: AnswerSource,
+    prompt_sink: PromptSink,
+    events: Option<EventStream>,
+    #[cfg(unix)]
+    fifo: Option<Fifo>,
+    status: Option<StatusFile>,
+    render: Render,
+}
+
+impl Console {
+    /// Show the output of the preview command, if there is one.
+    fn show_preview(&mut self) {
+        let Some(command) = &self.render.preview else {
+            return;
+        };
+        let output = match preview::run(command) {
+            Ok(output) => output,
+            Err(err) => {
+                if !self.render.quiet {
+                    eprintln!("{:#}", err);
+                }
+                return;
             }
-            SingleChar => {
-                let ch = getch::Getch::new().getch()?;
-                println!();
-                input_buf.push(ch as char);
+        };
+        // The question and a line to spare stay on the screen.
+        let room = self.prompt_sink.rows().map(|rows| rows.saturating_sub(2));
+        if self.render.pager && room.is_some_and(|room| output.lines().count() > room) {
+            let paged = self
+                .prompt_sink
+                .flush()
+                .and_then(|()| self.prompt_sink.stdio())
+                .map_err(anyhow::Error::from)
+                .and_then(|out| preview::page(&output, out));
+            match paged {
+                Ok(()) => return,
+                Err(err) if !self.render.quiet => eprintln!("{:#}", err),
+                Err(_) => {}
             }
+        }
+        let lines = match self.render.preview_fit {
+            true => room.map(|room| room.saturating_sub(1).max(1)),
+            false => None,
         };
+        let shown = self.render.display(&preview::excerpt(&output, lines));
+        // The question is still asked without it.
+        let _ = write!(self.prompt_sink, "{}", shown);
+    }
 
-        let response = input_buf.trim();
+    /// Play the sound cue for asking, if enabled.
+    fn play_cue(&self) {
+        #[cfg(feature = "sound")]
+        self.play(sound::Cue::Prompt);
+    }
 
-        if response.is_empty() {
-            Ok(self.default_response)
-        } else if self.use_full_words && !is_full_word(response) {
-            Err(anyhow!("Please type yes or no"))
-        } else {
-            Ok(Answer::from_str(response)?)
+    #[cfg(feature = "sound")]
+    fn play(&self, cue: sound::Cue) {
+        if self.render.sound {
+            if let Err(err) = sound::play(cue) {
+                if !self.render.quiet {
+                    eprintln!("Cannot play sound: {}", err);
+                }
+            }
         }
     }
 
-    fn get_user_input(&self, prompt: &str) -> Answer {
-        self.try_read_value(prompt).unwrap_or_else(|err| {
-            eprintln!("Error while reading user input: {}", err);
-            Answer::Retry
-        })
+    /// Ask the terminal to notify the user about `message`, if enabled.
+    fn draw_attention(&mut self, message: &str) {
+        if self.render.attention && self.prompt_sink.is_terminal() && !dumb_terminal() {
+            // Only a hint, the prompt itself is what matters.
+            let _ = write!(self.prompt_sink, "{}", attention::sequence(message));
+        }
     }
 
-    pub fn ask_loop(&self) -> bool {
-        let prompt = self.prepare_prompt();
-
-        macro_rules! ask {
-            () => {
-                match self.get_user_input(&prompt) {
-                    Answer::Yes => {
-                        return true;
-                    }
-                    Answer::No => {
-                        return false;
-                    }
-                    Answer::Retry => {}
-                };
+    /// Block until there's something to read, reminding the user every
+    /// renotify interval that passes without an answer, or until a line
+    /// comes through the FIFO.
+    #[cfg(unix)]
+    fn wait_for_answer(&mut self, prompt: &str) -> io::Result<Waited> {
+        let fd = self.answer_source.as_raw_fd();
+        let interval = self.render.renotify.filter(|_| term::is_terminal(fd));
+        let started = Instant::now();
+        let deadline = self.render.timeout.map(|timeout| started + timeout);
+        let mut reminder = interval.map(|interval| started + interval);
+        if self.answer_source.has_buffered() {
+            return Ok(Waited::Input);
+        }
+        loop {
+            if let Some(fifo) = &mut self.fifo {
+                if let Some(line) = fifo.read_line()? {
+                    return Ok(Waited::Fifo(line));
+                }
+            }
+            let until = match (deadline, reminder) {
+                (Some(deadline), Some(reminder)) => Some(deadline.min(reminder)),
+                (Some(at), None) | (None, Some(at)) => Some(at),
+                (None, None) => None,
             };
+            let fifo = self.fifo.as_ref().map(AsRawFd::as_raw_fd);
+            if until.is_none() && fifo.is_none() {
+                return Ok(Waited::Input);
+            }
+            let fds: Vec<_> = std::iter::once(fd).chain(fifo).collect();
+            let left = until.map(|until| until.saturating_duration_since(Instant::now()));
+            match term::wait_any(&fds, left)? {
+                Some(0) => return Ok(Waited::Input),
+                // Read at the top, it may not be a whole line yet.
+                Some(_) => continue,
+                None => {}
+            }
+            if deadline.is_some_and(|deadline| Instant::now() >= deadline) {
+                return Ok(Waited::TimedOut);
+            }
+            if reminder.is_none_or(|reminder| Instant::now() < reminder) {
+                continue;
+            }
+            reminder = interval.map(|interval| Instant::now() + interval);
+            let waited = format_wait(started.elapsed());
+            let catalog = self.render.catalog();
+            if self.render.accessible {
+                let notice = fill(catalog.still_waiting, &[&waited]);
+                writeln!(self.prompt_sink, "\x07{}", self.render.display(&notice))?;
+                write!(self.prompt_sink, "{}", prompt)?;
+            } else {
+                let notice = fill(catalog.waiting, &[&waited]);
+                write!(
+                    self.prompt_sink,
+                    "\x07\n{} {}",
+                    self.render.display(&notice),
+                    prompt
+                )?;
+            }
+            self.prompt_sink.flush()?;
+            self.play_cue();
+            self.draw_attention(&fill(catalog.still_waiting, &[&waited]));
         }
+    }
 
-        ask!(); // We always ask it at least once.
+    #[cfg(not(unix))]
+    fn wait_for_answer(&mut self, _prompt: &str) -> io::Result<Waited> {
+        Ok(Waited::Input)
+    }
 
-        match self.retry_mode {
-            TryMode::Infinite => loop {
-                ask!();
-            },
-            TryMode::Count(x) => {
-                for _ in 0..x.get() {
-                    ask!();
+    /// Take a line from the FIFO as the answer, as if it had been typed.
+    #[cfg(unix)]
+    fn fifo_answer(&mut self, line: String) -> io::Result<String> {
+        let fd = self.answer_source.as_raw_fd();
+        // Whatever was typed meanwhile shouldn't end up in the next program.
+        if term::is_terminal(fd) {
+            termios::tcflush(fd, termios::TCIFLUSH)?;
+        }
+        if let Some(fifo) = &self.fifo {
+            log::debug!("Answered through {}", fifo.path().display());
+        }
+        Ok(line)
+    }
+
+    /// Give up waiting, throwing away anything typed so far, before the
+    /// question takes its default, see [`Decision::TimedOut`].  The prompt
+    /// line is left for the caller to end.
+    #[cfg(unix)]
+    fn time_out(&mut self) -> io::Result<()> {
+        let fd = self.answer_source.as_raw_fd();
+        if term::is_terminal(fd) {
+            termios::tcflush(fd, termios::TCIFLUSH)?;
+        }
+        let waited = format_wait(self.render.timeout.unwrap_or_default());
+        self.message(&fill(self.render.catalog().timed_out, &[&waited]));
+        #[cfg(feature = "sound")]
+        self.play(sound::Cue::Timeout);
+        Ok(())
+    }
+
+    #[cfg(not(unix))]
+    fn time_out(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+
+    /// Give the user the grace period, if any, to take back a yes.
+    #[cfg(unix)]
+    fn hold_back(&mut self) -> Answer {
+        let Some(grace) = self.render.grace else {
+            return Answer::Yes;
+        };
+        if !term::is_terminal(self.answer_source.as_raw_fd()) {
+            log::debug!("Answers don't come from a terminal, no grace period");
+            return Answer::Yes;
+        }
+        match self.count_down(grace) {
+            Ok(true) => Answer::Yes,
+            Ok(false) => {
+                self.message(self.render.catalog().cancelled);
+                self.event(Event::Aborted {
+                    reason: "cancelled",
+                });
+                Answer::No
+            }
+            // Going ahead without the chance to cancel that was asked for
+            // would be worse than not going ahead.
+            Err(err) => {
+                self.message(&fill(self.render.catalog().read_error, &[&err.to_string()]));
+                Answer::No
+            }
+        }
+    }
+
+    /// Count down `grace`, returning false if a key was pressed meanwhile.
+    /// Ctrl-C counts as a key, the terminal is restored either way.
+    #[cfg(unix)]
+    fn count_down(&mut self, grace: Duration) -> io::Result<bool> {
+        let fd = self.answer_source.as_raw_fd();
+        let _raw = term::RawMode::enable_without_signals(fd)?;
+        let redraw = self.can_redraw() && !self.render.accessible;
+        let started = Instant::now();
+        let mut shown = None;
+        loop {
+            let left = grace.saturating_sub(started.elapsed());
+            if left.is_zero() {
+                if redraw {
+                    write!(self.prompt_sink, "\r\x1b[K")?;
+                    self.prompt_sink.flush()?;
                 }
-                eprintln!("Retry count exceeded.  Aborting...");
-                false
+                return Ok(true);
+            }
+            // Whole seconds, rounded up, so the count ends at 1s.
+            let secs = left.as_secs() + u64::from(left.subsec_nanos() > 0);
+            if shown != Some(secs) {
+                let notice = fill(
+                    self.render.catalog().proceeding,
+                    &[&format_wait(Duration::from_secs(secs))],
+                );
+                let notice = self.render.display(&notice);
+                match (redraw, shown) {
+                    (true, _) => write!(self.prompt_sink, "\r\x1b[K{}", notice)?,
+                    (false, None) => writeln!(self.prompt_sink, "{}", notice)?,
+                    (false, Some(_)) => {}
+                }
+                self.prompt_sink.flush()?;
+                shown = Some(secs);
+            }
+            let tick = left - Duration::from_secs(secs - 1);
+            if self.answer_source.has_buffered() || term::wait_readable(fd, tick)? {
+                termios::tcflush(fd, termios::TCIFLUSH)?;
+                if redraw {
+                    write!(self.prompt_sink, "\r\x1b[K")?;
+                    self.prompt_sink.flush()?;
+                }
+                return Ok(false);
+            }
+        }
+    }
+
+    /// Read a line on a terminal while a timeout counts down until the first
+    /// key.  Canonical mode only reports input once Enter is pressed, so the
+    /// line is read and edited key by key instead.
+    #[cfg(unix)]
+    fn read_line_pausing(&mut self, prompt: &str) -> io::Result<Option<String>> {
+        // The first key stays queued when the editor takes over, and the
+        // editor restores the terminal itself if interrupted.
+        let raw = term::RawMode::enable(self.answer_source.as_raw_fd())?;
+        let waited = self.wait_for_answer(prompt)?;
+        drop(raw);
+        match waited {
+            Waited::Input => {
+                editor::read_line(&mut self.answer_source, &mut self.prompt_sink, prompt, &[])
+            }
+            Waited::Fifo(line) => {
+                writeln!(
+                    self.prompt_sink,
+                    "{}",
+                    confirm_rs::text::sanitize(line.trim_end())
+                )?;
+                self.fifo_answer(line).map(Some)
+            }
+            Waited::TimedOut => {
+                self.time_out()?;
+                writeln!(self.prompt_sink)?;
+                Err(io::ErrorKind::TimedOut.into())
             }
         }
     }
 }
 
-impl From<MainOptions> for Confirm {
-    fn from(o: MainOptions) -> Self {
+impl Backend for Console {
+    fn show(&mut self, text: &str) -> io::Result<()> {
+        write!(self.prompt_sink, "{}", text)?;
+        self.prompt_sink.flush()
+    }
+
+    fn read_line(&mut self, prompt: &str) -> io::Result<Option<String>> {
+        #[cfg(unix)]
+        if self.render.timeout.is_some()
+            && self.render.timeout_mode == TimeoutMode::Pause
+            && term::is_terminal(self.answer_source.as_raw_fd())
+            && !dumb_terminal()
+        {
+            return self.read_line_pausing(prompt);
+        }
+        match self.wait_for_answer(prompt)? {
+            Waited::Input => {}
+            #[cfg(unix)]
+            Waited::Fifo(line) => {
+                // Typed lines are echoed by the terminal, this one isn't.
+                writeln!(
+                    self.prompt_sink,
+                    "{}",
+                    confirm_rs::text::sanitize(line.trim_end())
+                )?;
+                return self.fifo_answer(line).map(Some);
+            }
+            Waited::TimedOut => {
+                self.time_out()?;
+                writeln!(self.prompt_sink)?;
+                return Err(io::ErrorKind::TimedOut.into());
+            }
+        }
+        let mut line = String::new();
+        Ok(match self.answer_source.read_line(&mut line)? {
+            0 => None,
+            _ => Some(line),
+        })
+    }
+
+    fn read_key(&mut self, prompt: &str) -> io::Result<Option<char>> {
+        // Keys often only arrive with Enter there anyway, like in Emacs.
+        if dumb_terminal() {
+            let reason = "the terminal is dumb";
+            return Err(io::Error::new(io::ErrorKind::Unsupported, reason));
+        }
+        #[cfg(unix)]
+        let _raw = term::RawMode::enable(self.answer_source.as_raw_fd())
+            .map_err(|err| io::Error::new(io::ErrorKind::Unsupported, err))?;
+        match self.wait_for_answer(prompt)? {
+            Waited::Input => {}
+            #[cfg(unix)]
+            Waited::Fifo(line) => {
+                let line = self.fifo_answer(line)?;
+                return Ok(Some(line.chars().next().unwrap_or('\n')));
+            }
+            Waited::TimedOut => {
+                self.time_out()?;
+                return Err(io::ErrorKind::TimedOut.into());
+            }
+        }
+        Ok(match self.answer_source.read_key()? {
+            0 => None,
+            ch => Some(ch as char),
+        })
+    }
+
+    fn can_redraw(&self) -> bool {
+        self.prompt_sink.is_terminal() && !dumb_terminal()
+    }
+
+    fn columns(&self) -> Option<usize> {
+        self.prompt_sink.columns()
+    }
+
+    fn event(&mut self, event: Event) {
+        if let (Some(status), Event::PromptShown { attempt, .. }) = (&self.status, &event) {
+            if let Err(err) = status.update(*attempt) {
+                let path = status.path().display().to_string();
+                self.message(&format!("Cannot write the status file {}: {}", path, err));
+            }
+        }
+        if let Some(events) = &mut self.events {
+            events.emit(event);
+        }
+    }
+
+    fn message(&mut self, text: &str) {
+        if !self.render.quiet {
+            eprintln!("{}", self.render.display(text));
+        }
+    }
+}
+
+impl TryFrom<MainOptions> for Confirm {
+    type Error = anyhow::Error;
+
+    fn try_from(o: MainOptions) -> Result<Self> {
         o.into_confirm()
     }
 }
 
-fn main() {
-    let opts = MainOptions::parse();
+/// Exit code for --require-tty without a terminal.
+const NO_TERMINAL_EXIT: i32 = 4;
+
+/// Exit code for the neutral answer unless --neutral-code says otherwise.
+const DEFAULT_NEUTRAL_CODE: u8 = 3;
+
+/// Get the answer the options ask for, adding it to the audit log if there
+/// is one.
+fn ask(opts: MainOptions) -> Result<Answer> {
+    let asks = !(opts.always_yes || opts.always_no);
+    let logged = opts
+        .audit_log
+        .is_some()
+        .then(|| (opts.clone(), Instant::now()));
+    let answer = get_answer(opts)?;
+    if let Some((opts, started)) = logged {
+        let response = asks.then(|| started.elapsed());
+        if let Err(err) = audit::record(&opts, answer.as_str(), response) {
+            if !opts.quiet {
+                eprintln!("{:#}", err);
+            }
+        }
+    }
+    Ok(answer)
+}
+
+fn get_answer(opts: MainOptions) -> Result<Answer> {
     if opts.always_yes {
-        return;
+        return Ok(Answer::Yes);
     } else if opts.always_no {
-        std::process::exit(1)
+        return Ok(Answer::No);
     }
-    if atty::isnt(atty::Stream::Stdin) {
-        eprintln!("Warning: using confirm when stdin is not a tty is not supported.");
+    #[cfg(all(feature = "desktop", unix, not(target_os = "macos")))]
+    if opts.desktop {
+        return desktop::ask(&opts.prompt, opts.default, opts.neutral.as_deref());
     }
-    let confirmed = Confirm::from(opts).ask_loop();
-    if !confirmed {
-        std::process::exit(1);
+    if !opts.quiet && !opts.stdin_answer && opts.reads_stdin() && atty::isnt(atty::Stream::Stdin) {
+        let render = opts.render();
+        eprintln!("{}", render.display(render.catalog().not_a_tty));
+    }
+    Ok(Confirm::try_from(opts)?.ask_loop().answer())
+}
+
+fn main() -> Result<()> {
+    let opts = config::parse_options()?;
+    opts.check()?;
+    if opts.render_only {
+        let prompt = opts.into_question().render_prompt();
+        match prompt.ends_with('\n') {
+            true => print!("{}", prompt),
+            false => println!("{}", prompt),
+        }
+        return Ok(());
+    }
+    if opts.require_tty && !(opts.always_yes || opts.always_no) && !opts.answers_from_terminal() {
+        if !opts.quiet {
+            let render = opts.render();
+            eprintln!("{}", render.display(render.catalog().no_terminal));
+        }
+        std::process::exit(NO_TERMINAL_EXIT);
+    }
+    // Only the paths that ask take turns, self-update once it's about to.
+    let asks = match &opts.command {
+        None | Some(Command::Wizard(_) | Command::Choose(_)) => true,
+        #[cfg(target_os = "linux")]
+        Some(Command::SystemdAgent(_)) => true,
+        #[cfg(unix)]
+        Some(Command::Gate(_) | Command::Filter(_)) => true,
+        _ => false,
+    };
+    // Held until the process exits.
+    let _lock = match asks {
+        true => opts.take_lock()?,
+        false => None,
+    };
+    if let Some(command) = &opts.command {
+        return match command {
+            #[cfg(target_os = "linux")]
+            Command::SystemdAgent(agent) => systemd::run(&opts, agent),
+            Command::Wizard(wizard) => wizard::run(&opts, wizard),
+            Command::Hook(hook) => hook::run(hook),
+            #[cfg(unix)]
+            Command::Gate(gate) => gate::run(&opts, gate),
+            #[cfg(unix)]
+            Command::Filter(filter) => filter::run(&opts, filter),
+            Command::Choose(choose) => choose::run(&opts, choose),
+            #[cfg(unix)]
+            Command::Selftest => selftest::run(),
+            Command::Doctor => doctor::run(&opts),
+            #[cfg(feature = "self-update")]
+            Command::SelfUpdate(update) => self_update::run(&opts, update),
+            Command::History(history) => audit::run(&opts, history),
+        };
+    }
+    let shell_output = opts.shell_output;
+    #[cfg(unix)]
+    let json_request = opts.json_request;
+    #[cfg(unix)]
+    let (signal_pid, signal_yes, signal_no) = (opts.signal_pid, opts.signal, opts.signal_no);
+    let neutral_code = opts.neutral_code;
+    let answer = match opts.group.clone() {
+        Some(key) if !(opts.always_yes || opts.always_no) => group::ask(&key, opts)?,
+        _ => ask(opts)?,
     };
+    if shell_output {
+        let vars = [(shell::ANSWER_VAR.to_string(), answer.as_str())];
+        println!("{}", shell::report(&vars));
+    }
+    #[cfg(unix)]
+    if json_request {
+        println!("{}", serde_json::json!({ "answer": answer.as_str() }));
+    }
+    #[cfg(unix)]
+    if let Some(pid) = signal_pid {
+        match answer {
+            Answer::Yes => signal::send(pid, signal_yes)?,
+            Answer::No => signal_no.map_or(Ok(()), |sig| signal::send(pid, sig))?,
+            _ => {}
+        }
+    }
+    finish(answer, neutral_code)
+}
+
+/// Exit with the status for `answer`.
+fn finish(answer: Answer, neutral_code: u8) -> Result<()> {
+    match answer {
+        Answer::Yes => Ok(()),
+        Answer::Neutral => std::process::exit(neutral_code.into()),
+        _ => std::process::exit(1),
+    }
 }
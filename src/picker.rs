@@ -0,0 +1,206 @@
+//! Picking from a list by typing to narrow it down, fzf-style.
+//!
+//! Typed characters filter the options by fuzzy matching: an option matches
+//! if it contains them in order, and the closer together and the earlier
+//! they are, the higher it is listed.  The arrow keys move the selection,
+//! enter picks it and escape gives up.
+//!
+//! Lists longer than fit on the terminal scroll under the pinned question,
+//! with the position shown after the query and page up and down moving a
+//! screenful at a time.
+
+use std::io::{self, Write};
+use std::os::fd::AsRawFd;
+
+use confirm_rs::text;
+use unicode_width::UnicodeWidthChar;
+
+use crate::term::{AnswerSource, Key, PromptSink, RawMode};
+
+/// How many options are listed at most, however tall the terminal.
+const VISIBLE: usize = 10;
+
+/// Rows that aren't options: the question, the query and one to spare, so
+/// the terminal doesn't scroll when the cursor reaches the bottom.
+const RESERVED_ROWS: usize = 3;
+
+/// How well `query` matches `option`, higher being better, or `None` if it
+/// doesn't.  Case is ignored.
+fn score(query: &str, option: &str) -> Option<i64> {
+    let option: Vec<char> = option.chars().flat_map(char::to_lowercase).collect();
+    let mut score = 0;
+    let mut from = 0;
+    let mut last = None;
+    for ch in query.chars().flat_map(char::to_lowercase) {
+        let found = from + option[from..].iter().position(|&c| c == ch)?;
+        score -= found as i64;
+        if last == Some(found.wrapping_sub(1)) {
+            score += 10;
+        }
+        if found == 0 || !option[found - 1].is_alphanumeric() {
+            score += 5;
+        }
+        last = Some(found);
+        from = found + 1;
+    }
+    Some(score)
+}
+
+/// The indexes of the options matching `query`, best first, keeping the
+/// original order among equals.
+fn matching(query: &str, options: &[String]) -> Vec<usize> {
+    let mut scored: Vec<(i64, usize)> = options
+        .iter()
+        .enumerate()
+        .filter_map(|(i, option)| score(query, option).map(|score| (score, i)))
+        .collect();
+    scored.sort_by_key(|&(score, i)| (-score, i));
+    scored.into_iter().map(|(_, i)| i).collect()
+}
+
+/// Cut `text` to fit in `columns`, so no line wraps and the redraw stays
+/// in place.
+fn fit(text: &str, columns: usize) -> String {
+    let mut width = 0;
+    text.chars()
+        .take_while(|ch| {
+            width += ch.width().unwrap_or(0);
+            width <= columns
+        })
+        .collect()
+}
+
+struct List<'a> {
+    options: &'a [String],
+    query: String,
+    matches: Vec<usize>,
+    /// The position of the selection in `matches`.
+    selected: usize,
+    /// The position in `matches` of the first option shown.
+    top: usize,
+    /// How many options fit on the screen.
+    visible: usize,
+    /// How many option lines are drawn below the query.
+    drawn: usize,
+}
+
+impl List<'_> {
+    fn filter(&mut self) {
+        self.matches = matching(&self.query, self.options);
+        self.selected = 0;
+    }
+
+    fn select(&mut self, by: isize) {
+        let last = self.matches.len().saturating_sub(1);
+        self.selected = self.selected.saturating_add_signed(by).min(last);
+    }
+
+    /// Scroll just enough for the selection to be shown.
+    fn scroll(&mut self) {
+        if self.selected < self.top {
+            self.top = self.selected;
+        } else if self.selected >= self.top + self.visible {
+            self.top = self.selected + 1 - self.visible;
+        }
+        self.top = self
+            .top
+            .min(self.matches.len().saturating_sub(self.visible));
+    }
+
+    /// Draw the query line and the options around the selection, leaving
+    /// the cursor after the query.
+    fn draw(&mut self, sink: &mut PromptSink, marker: &str) -> io::Result<()> {
+        let columns = sink.columns().unwrap_or(80).saturating_sub(3);
+        self.visible = sink
+            .rows()
+            .map_or(VISIBLE, |rows| rows.saturating_sub(RESERVED_ROWS))
+            .clamp(1, VISIBLE);
+        self.scroll();
+        write!(sink, "\r\x1b[J{} {}", marker, self.query)?;
+        if self.matches.len() > self.visible {
+            write!(
+                sink,
+                "  \x1b[2m{}/{}\x1b[0m",
+                self.selected + 1,
+                self.matches.len()
+            )?;
+        }
+        self.drawn = 0;
+        for (at, &i) in self
+            .matches
+            .iter()
+            .enumerate()
+            .skip(self.top)
+            .take(self.visible)
+        {
+            let option = fit(&self.options[i], columns);
+            match at == self.selected {
+                true => write!(sink, "\n\x1b[7m{} {}\x1b[0m", marker, option)?,
+                false => write!(sink, "\n  {}", option)?,
+            }
+            self.drawn += 1;
+        }
+        if self.drawn > 0 {
+            write!(sink, "\x1b[{}A", self.drawn)?;
+        }
+        let column = text::display_width(marker) + 1 + text::display_width(&self.query);
+        write!(sink, "\r\x1b[{}C", column)?;
+        sink.flush()
+    }
+}
+
+/// Let the user pick one of `options` on the terminal, starting at
+/// `default`.  Returns `None` on escape or at the end of input.  Options
+/// should already be safe to show, see [`text::sanitize`].
+pub fn pick(
+    source: &mut AnswerSource,
+    sink: &mut PromptSink,
+    options: &[String],
+    default: Option<usize>,
+    marker: &str,
+) -> io::Result<Option<usize>> {
+    let raw = RawMode::enable_without_signals(source.as_raw_fd())?;
+    let mut list = List {
+        options,
+        query: String::new(),
+        matches: (0..options.len()).collect(),
+        selected: default.unwrap_or(0),
+        top: 0,
+        visible: VISIBLE,
+        drawn: 0,
+    };
+    loop {
+        list.draw(sink, marker)?;
+        match source.read_key_event()? {
+            Key::Char(ch) if !ch.is_control() => {
+                list.query.push(ch);
+                list.filter();
+            }
+            Key::Backspace if !list.query.is_empty() => {
+                list.query.pop();
+                list.filter();
+            }
+            Key::Up => list.select(-1),
+            Key::Down => list.select(1),
+            Key::PageUp => list.select(-(list.visible as isize)),
+            Key::PageDown => list.select(list.visible as isize),
+            Key::Enter if !list.matches.is_empty() => {
+                let picked = list.matches[list.selected];
+                write!(sink, "\r\x1b[J{} {}\n", marker, options[picked])?;
+                return Ok(Some(picked));
+            }
+            Key::Escape | Key::Eof => {
+                write!(sink, "\r\x1b[J\n")?;
+                return Ok(None);
+            }
+            Key::Interrupt => {
+                write!(sink, "\r\x1b[J\n")?;
+                drop(raw);
+                // SAFETY: raising a signal on ourselves has no preconditions.
+                unsafe { libc::raise(libc::SIGINT) };
+                return Err(io::ErrorKind::Interrupted.into());
+            }
+            _ => {}
+        }
+    }
+}
@@ -0,0 +1,92 @@
+//! Running the `--if`, `--unless` and `--check` commands before asking.
+//!
+//! The guards decide whether to ask at all: unless `--if` succeeds and
+//! `--unless` fails, confirm answers "yes" without asking, replacing the
+//! `if ...; then confirm ...; fi` around it in scripts.
+//!
+//! The check runs through the shell before anything is asked: exiting 0
+//! makes "yes" the default, anything else "no", so e.g. `confirm --check
+//! "make test" "Deploy?"` leans towards not deploying a failing build.  Its
+//! stdout is kept off the terminal, except for the first line shown after
+//! the prompt with `--check-output`.
+//!
+//! Only the command line can give these commands, never the configuration
+//! files, so a checked out `.confirm.toml` can't make confirm run commands.
+
+use std::process::{Command, Stdio};
+
+use anyhow::{Context, Result};
+
+use crate::{Answer, MainOptions};
+
+pub fn shell(command: &str) -> Command {
+    let (program, flag) = match cfg!(windows) {
+        true => ("cmd", "/C"),
+        false => ("sh", "-c"),
+    };
+    let mut shell = Command::new(program);
+    shell.args([flag, command]);
+    shell
+}
+
+/// Whether the guard `command` succeeds, with its output kept off stdout.
+fn succeeds(command: &str) -> Result<bool> {
+    let status = shell(command)
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .status()
+        .with_context(|| format!("Cannot run the guard {:?}", command))?;
+    log::debug!("The guard {:?} exited with {}", command, status);
+    Ok(status.success())
+}
+
+/// Whether the guards leave the question to be asked.
+fn guards_pass(opts: &MainOptions) -> Result<bool> {
+    if let Some(command) = &opts.if_command {
+        if !succeeds(command)? {
+            return Ok(false);
+        }
+    }
+    match &opts.unless_command {
+        Some(command) => Ok(!succeeds(command)?),
+        None => Ok(true),
+    }
+}
+
+/// Run the guards and the check, answering without asking if the guards
+/// say so, and set the default answer and prompt from the check.
+pub fn apply(opts: &mut MainOptions) -> Result<()> {
+    if !guards_pass(opts)? {
+        log::debug!("Not asking, answering yes");
+        opts.always_yes = true;
+        return Ok(());
+    }
+    let Some(command) = &opts.check else {
+        return Ok(());
+    };
+    let output = shell(command)
+        .stdin(Stdio::null())
+        .stderr(Stdio::inherit())
+        .output()
+        .with_context(|| format!("Cannot run the check {:?}", command))?;
+    log::debug!("The check {:?} exited with {}", command, output.status);
+    opts.default = match output.status.success() {
+        true => Answer::Yes,
+        false => Answer::No,
+    };
+    if opts.check_output {
+        let stdout = String::from_utf8_lossy(&output.stdout);
+        let first_line = stdout
+            .lines()
+            .map(|line| {
+                line.chars()
+                    .filter(|ch| !ch.is_control())
+                    .collect::<String>()
+            })
+            .find(|line| !line.trim().is_empty());
+        if let Some(line) = first_line {
+            opts.prompt = format!("{} ({})", opts.prompt, line.trim());
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,72 @@
+//! Showing what is being confirmed above the question: the output of the
+//! `--preview` command, or the input held back by `confirm gate`.
+
+use std::io::{ErrorKind, Write};
+use std::process::Stdio;
+
+use anyhow::{Context, Result};
+use confirm_rs::text;
+
+use crate::precheck::shell;
+
+/// Run the preview `command` through the shell and return what it prints.
+/// It fails only if the command can't be started, since a failing diff or
+/// plan can still be worth seeing.
+pub fn run(command: &str) -> Result<String> {
+    let output = shell(command)
+        .stdin(Stdio::null())
+        .stderr(Stdio::inherit())
+        .output()
+        .with_context(|| format!("Cannot run the preview {:?}", command))?;
+    log::debug!("The preview {:?} exited with {}", command, output.status);
+    Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+/// Show `text` through the user's pager writing to `out`, and wait for it
+/// to be closed.
+pub fn page(text: &str, out: Stdio) -> Result<()> {
+    let default = match cfg!(windows) {
+        true => "more",
+        false => "less",
+    };
+    let pager = std::env::var("PAGER")
+        .ok()
+        .filter(|pager| !pager.trim().is_empty())
+        .unwrap_or_else(|| default.to_string());
+    let mut child = shell(&pager)
+        .stdin(Stdio::piped())
+        .stdout(out)
+        .spawn()
+        .with_context(|| format!("Cannot run the pager {:?}", pager))?;
+    if let Some(mut stdin) = child.stdin.take() {
+        match stdin.write_all(text::sanitize(text).as_bytes()) {
+            // Quitting the pager early closes the pipe.
+            Err(err) if err.kind() != ErrorKind::BrokenPipe => {
+                return Err(err).context("Cannot write to the pager");
+            }
+            _ => {}
+        }
+    }
+    let status = child.wait()?;
+    log::debug!("The pager {:?} exited with {}", pager, status);
+    Ok(())
+}
+
+/// `text` indented as shown on the terminal, cut to `lines` with a count of
+/// what's left out.  Escape sequences are removed, so what's shown can't
+/// disguise itself or the question.
+pub fn excerpt(text: &str, lines: Option<usize>) -> String {
+    let total = text.lines().count();
+    let lines = lines.unwrap_or(total);
+    let mut shown: String = text
+        .lines()
+        .take(lines)
+        .map(|line| format!("  {}\n", text::sanitize(line)))
+        .collect();
+    match total.saturating_sub(lines) {
+        0 => {}
+        1 => shown.push_str("  ... 1 more line\n"),
+        more => shown.push_str(&format!("  ... {} more lines\n", more)),
+    }
+    shown
+}
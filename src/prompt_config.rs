@@ -0,0 +1,160 @@
+//! Questions as data, for reading them from configuration files or storing
+//! them alongside an embedder's own settings.
+
+use serde::de::{self, Visitor};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+use crate::i18n::Lang;
+use crate::{Answer, Highlight, Icons, Question, ReaderType, TryMode};
+
+/// The settings of a [`Question`], spelled like the command line options.
+/// Anything left out keeps the command line tool's default.
+///
+/// ```
+/// use confirm_rs::{Answer, PromptConfig, Question};
+///
+/// let config: PromptConfig = serde_json::from_str(
+///     r#"{"prompt": "Deploy now?", "default": "no", "retries": "once"}"#,
+/// ).unwrap();
+/// let question = Question::from(config);
+/// assert_eq!(question.default, Answer::No);
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default, rename_all = "kebab-case", deny_unknown_fields)]
+pub struct PromptConfig {
+    pub prompt: String,
+    pub default: Answer,
+    /// Answer with a single key, without pressing enter.
+    pub no_enter: bool,
+    pub retries: TryMode,
+    pub full_words: bool,
+    pub boolean_aliases: bool,
+    pub accessible: bool,
+    pub ascii: bool,
+    pub lang: Lang,
+    pub highlight: Highlight,
+    pub icons: Option<Icons>,
+    pub neutral: Option<String>,
+    pub danger: bool,
+    pub require_match: Option<String>,
+    pub tries_format: Option<String>,
+}
+
+impl Default for PromptConfig {
+    fn default() -> Self {
+        Self::from(&Question::new(""))
+    }
+}
+
+impl From<&Question> for PromptConfig {
+    fn from(question: &Question) -> Self {
+        Self {
+            prompt: question.prompt.clone(),
+            default: question.default,
+            no_enter: question.reader_type == ReaderType::SingleChar,
+            retries: question.retry_mode,
+            full_words: question.full_words,
+            boolean_aliases: question.boolean_aliases,
+            accessible: question.accessible,
+            ascii: question.ascii,
+            lang: question.lang,
+            highlight: question.highlight,
+            icons: question.icons.clone(),
+            neutral: question.neutral.clone(),
+            danger: question.danger,
+            require_match: question.require_match.clone(),
+            tries_format: question.tries_format.clone(),
+        }
+    }
+}
+
+impl From<PromptConfig> for Question {
+    fn from(config: PromptConfig) -> Self {
+        Self {
+            prompt: config.prompt,
+            default: config.default,
+            reader_type: match config.no_enter {
+                true => ReaderType::SingleChar,
+                false => ReaderType::NewlineBuffered,
+            },
+            retry_mode: config.retries,
+            full_words: config.full_words,
+            boolean_aliases: config.boolean_aliases,
+            accessible: config.accessible,
+            ascii: config.ascii,
+            lang: config.lang,
+            highlight: config.highlight,
+            icons: config.icons,
+            neutral: config.neutral,
+            danger: config.danger,
+            require_match: config.require_match,
+            tries_format: config.tries_format,
+            validator: None,
+        }
+    }
+}
+
+/// The number of retries, with 0 for no limit as on the command line, or
+/// `"infinite"` or `"once"`.
+impl Serialize for TryMode {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match self {
+            TryMode::Count(count) => serializer.serialize_u8(count.get()),
+            TryMode::Infinite => serializer.serialize_str("infinite"),
+            TryMode::Once => serializer.serialize_str("once"),
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for TryMode {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        struct TryModeVisitor;
+
+        impl Visitor<'_> for TryModeVisitor {
+            type Value = TryMode;
+
+            fn expecting(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+                f.write_str("a number of retries, \"infinite\" or \"once\"")
+            }
+
+            fn visit_u64<E: de::Error>(self, count: u64) -> Result<TryMode, E> {
+                u8::try_from(count)
+                    .map(TryMode::from)
+                    .map_err(|_| E::custom(format!("{} retries, at most 255 are allowed", count)))
+            }
+
+            // Formats like TOML only have signed integers.
+            fn visit_i64<E: de::Error>(self, count: i64) -> Result<TryMode, E> {
+                u64::try_from(count)
+                    .map_err(|_| E::custom("a negative number of retries"))
+                    .and_then(|count| self.visit_u64(count))
+            }
+
+            fn visit_str<E: de::Error>(self, mode: &str) -> Result<TryMode, E> {
+                match mode {
+                    "infinite" => Ok(TryMode::Infinite),
+                    "once" => Ok(TryMode::Once),
+                    _ => Err(E::unknown_variant(mode, &["infinite", "once"])),
+                }
+            }
+        }
+
+        deserializer.deserialize_any(TryModeVisitor)
+    }
+}
+
+/// The language code, like `"de"`.  Locale names like `de_DE.UTF-8` are
+/// accepted too.
+impl Serialize for Lang {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(self.code())
+    }
+}
+
+impl<'de> Deserialize<'de> for Lang {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        String::deserialize(deserializer)?
+            .parse()
+            .map_err(de::Error::custom)
+    }
+}
@@ -0,0 +1,119 @@
+//! Customizing the question with a Rhai script, for `--script`.
+//!
+//! The script may define any of these functions, and confirm calls those it
+//! finds:
+//!
+//! - `prompt(text)` returns the prompt to show instead of `text`.
+//! - `classify(input)` returns "yes", "no", "neutral" or "retry" for a
+//!   non-empty answer, or nothing to go by the usual words.  "neutral" is
+//!   only accepted with `--neutral`, since the prompt doesn't offer it
+//!   otherwise.
+//! - `tries()` returns how many times to ask, 0 for no limit, as with
+//!   `--ask-count`.
+//!
+//! Rhai can't run commands or reach the network, and `import` is turned off
+//! so no other files are read, so a script can only change how the question
+//! is asked.  What it prints goes to
+//! stderr, keeping stdout for the answer.
+
+use std::path::Path;
+use std::sync::Arc;
+
+use anyhow::{anyhow, bail, Context, Result};
+use confirm_rs::{Answer, Validator};
+use rhai::module_resolvers::DummyModuleResolver;
+use rhai::{Dynamic, Engine, FuncArgs, Scope, AST};
+
+use crate::MainOptions;
+
+/// Enough for any sensible script, while stopping an endless loop quickly.
+const MAX_OPERATIONS: u64 = 1_000_000;
+
+struct Script {
+    engine: Engine,
+    ast: AST,
+}
+
+impl Script {
+    fn load(path: &Path) -> Result<Self> {
+        let mut engine = Engine::new();
+        engine.set_max_operations(MAX_OPERATIONS);
+        engine.set_module_resolver(DummyModuleResolver::new());
+        engine.on_print(|text| eprintln!("{}", text));
+        engine.on_debug(|text, _, pos| log::debug!("Script at {}: {}", pos, text));
+        let ast = engine
+            .compile_file(path.to_path_buf())
+            .map_err(|err| anyhow!("{}", err))
+            .with_context(|| format!("Cannot load the script {}", path.display()))?;
+        Ok(Self { engine, ast })
+    }
+
+    fn defines(&self, name: &str) -> bool {
+        self.ast
+            .iter_functions()
+            .any(|function| function.name == name)
+    }
+
+    fn call(&self, name: &str, args: impl FuncArgs) -> Result<Dynamic> {
+        self.engine
+            .call_fn(&mut Scope::new(), &self.ast, name, args)
+            .map_err(|err| anyhow!("The script's {}() failed: {}", name, err))
+    }
+
+    fn classify(&self, input: &str, neutral: bool) -> Result<Option<Answer>> {
+        let result = self.call("classify", (input.to_string(),))?;
+        if result.is_unit() {
+            return Ok(None);
+        }
+        let answer = result
+            .into_string()
+            .map_err(|kind| anyhow!("classify() returned a {} instead of a string", kind))?;
+        match answer.to_ascii_lowercase().as_str() {
+            "yes" => Ok(Some(Answer::Yes)),
+            "no" => Ok(Some(Answer::No)),
+            "neutral" if neutral => Ok(Some(Answer::Neutral)),
+            "neutral" => bail!("classify() returned \"neutral\", but there's no --neutral answer"),
+            "retry" => Ok(Some(Answer::Retry)),
+            _ => bail!(
+                "classify() returned {:?}, expected yes, no, neutral or retry",
+                answer
+            ),
+        }
+    }
+}
+
+/// Load the `--script` and let it change the prompt, the number of tries
+/// and how answers are understood.
+pub fn apply(opts: &mut MainOptions) -> Result<()> {
+    let Some(path) = &opts.script else {
+        return Ok(());
+    };
+    let script = Script::load(path)?;
+    if script.defines("prompt") {
+        opts.prompt = script
+            .call("prompt", (opts.prompt.clone(),))?
+            .into_string()
+            .map_err(|kind| anyhow!("prompt() returned a {} instead of a string", kind))?;
+    }
+    if script.defines("tries") {
+        let tries = script
+            .call("tries", ())?
+            .as_int()
+            .map_err(|kind| anyhow!("tries() returned a {} instead of a number", kind))?;
+        opts.ask_count = u8::try_from(tries)
+            .map_err(|_| anyhow!("tries() returned {}, expected 0 to 255", tries))?;
+    }
+    if script.defines("classify") {
+        let script = Arc::new(script);
+        let (quiet, neutral) = (opts.quiet, opts.neutral.is_some());
+        opts.script_validator = Some(Validator::new(move |input| {
+            script.classify(input, neutral).unwrap_or_else(|err| {
+                if !quiet {
+                    eprintln!("{:#}", err);
+                }
+                Some(Answer::Retry)
+            })
+        }));
+    }
+    Ok(())
+}
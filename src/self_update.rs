@@ -0,0 +1,246 @@
+//! Replacing the installed executable with the latest release.
+//!
+//! Each release on GitHub carries one executable per platform, named
+//! `confirm-<arch>-<os>` (with `.exe` on Windows) after Rust's names for
+//! them, like `confirm-x86_64-linux`, a `SHA256SUMS` file listing their
+//! checksums, and `SHA256SUMS.minisig`, its minisign signature.  Downloads
+//! go through `curl`, which the supported platforms ship, rather than
+//! linking an HTTP and TLS stack into a tool this small.
+//!
+//! The signature is checked against the release key built into confirm,
+//! given as `CONFIRM_RELEASE_KEY` (a minisign public key) when building the
+//! release executables, and only then the executable against its checksum,
+//! so a release replaced by someone without the key is refused, not just a
+//! corrupted download.  Builds without a key, like those from
+//! `cargo install`, and releases without these assets only report newer
+//! releases.
+//!
+//! The new executable is staged next to the old one under a name of its
+//! own before it's renamed over it, so a failed update leaves the old one
+//! working.
+
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::Path;
+use std::process::{Command, Stdio};
+
+use anyhow::{anyhow, bail, Context, Result};
+use clap::Args;
+use minisign_verify::{PublicKey, Signature};
+use serde::Deserialize;
+use sha2::{Digest, Sha256};
+
+use crate::{finish, Answer, MainOptions};
+
+const LATEST_RELEASE: &str = "https://api.github.com/repos/scruffystuffs/confirm/releases/latest";
+
+/// The release asset listing the checksums of the others.
+const CHECKSUMS: &str = "SHA256SUMS";
+
+/// The release asset signing [`CHECKSUMS`].
+const SIGNATURE: &str = "SHA256SUMS.minisig";
+
+/// The minisign public key releases are signed with, if this build has one.
+const RELEASE_KEY: Option<&str> = option_env!("CONFIRM_RELEASE_KEY");
+
+#[derive(Clone, Debug, Args)]
+pub struct SelfUpdateOptions {
+    /// Only report whether a newer release is available
+    #[arg(long)]
+    check_only: bool,
+}
+
+#[derive(Debug, Deserialize)]
+struct Release {
+    tag_name: String,
+    assets: Vec<Asset>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Asset {
+    name: String,
+    browser_download_url: String,
+}
+
+impl Release {
+    fn url(&self, name: &str) -> Option<&str> {
+        self.assets
+            .iter()
+            .find(|asset| asset.name == name)
+            .map(|asset| asset.browser_download_url.as_str())
+    }
+}
+
+/// Download `url` over HTTPS.
+fn fetch(url: &str) -> Result<Vec<u8>> {
+    let output = Command::new("curl")
+        .args(["--fail", "--silent", "--show-error", "--location"])
+        .args(["--proto", "=https", url])
+        .stdin(Stdio::null())
+        .output()
+        .context("Cannot run curl to download the update")?;
+    if !output.status.success() {
+        bail!(
+            "Cannot download {}: {}",
+            url,
+            String::from_utf8_lossy(&output.stderr).trim()
+        );
+    }
+    Ok(output.stdout)
+}
+
+/// The numbers of a version like "v1.2.0", for comparing.
+fn version(text: &str) -> Vec<u64> {
+    text.trim_start_matches('v')
+        .split(['.', '-', '+'])
+        .map_while(|part| part.parse().ok())
+        .collect()
+}
+
+/// The name of this platform's executable in a release.
+fn asset_name() -> String {
+    format!(
+        "confirm-{}-{}{}",
+        std::env::consts::ARCH,
+        std::env::consts::OS,
+        std::env::consts::EXE_SUFFIX
+    )
+}
+
+/// Look up `name` in a checksum file written by `sha256sum`.
+fn listed_checksum(sums: &str, name: &str) -> Option<String> {
+    sums.lines().find_map(|line| {
+        let (checksum, file) = line.split_once(char::is_whitespace)?;
+        let file = file.trim_start().trim_start_matches('*');
+        (file == name).then(|| checksum.to_ascii_lowercase())
+    })
+}
+
+/// Check that `sums` was signed with the release key.
+fn verify(key: &str, sums: &[u8], signature: &[u8]) -> Result<()> {
+    let key = PublicKey::from_base64(key)
+        .map_err(|err| anyhow!("The built-in release key is invalid: {}", err))?;
+    let signature = Signature::decode(&String::from_utf8_lossy(signature))
+        .map_err(|err| anyhow!("Cannot read {}: {}", SIGNATURE, err))?;
+    key.verify(sums, &signature, false).map_err(|err| {
+        anyhow!(
+            "{} isn't signed with the release key ({}), leaving confirm as it is",
+            CHECKSUMS,
+            err
+        )
+    })
+}
+
+fn sha256(data: &[u8]) -> String {
+    Sha256::digest(data)
+        .iter()
+        .map(|byte| format!("{:02x}", byte))
+        .collect()
+}
+
+/// A name next to `exe` for this process alone, ending in `.<extension>`.
+fn sibling(exe: &Path, extension: &str) -> std::path::PathBuf {
+    exe.with_file_name(format!(".confirm.{}.{}", std::process::id(), extension))
+}
+
+/// Write `new` to a file that didn't exist before, never through a symlink.
+fn stage(staged: &Path, new: &[u8]) -> std::io::Result<()> {
+    let mut options = OpenOptions::new();
+    options.write(true).create_new(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.mode(0o755);
+    }
+    let mut file = options.open(staged)?;
+    file.write_all(new)?;
+    file.sync_all()
+}
+
+/// Put `new` in place of the executable at `exe`.
+fn replace(exe: &Path, new: &[u8]) -> Result<()> {
+    let staged = sibling(exe, "new");
+    if let Err(err) = stage(&staged, new) {
+        let _ = fs::remove_file(&staged);
+        return Err(err).with_context(|| format!("Cannot write {}", staged.display()));
+    }
+    // A running executable can't be replaced on Windows, only moved aside,
+    // and removed by a later update once it's no longer running.
+    #[cfg(windows)]
+    {
+        let dir = exe.parent().and_then(|dir| fs::read_dir(dir).ok());
+        for entry in dir.into_iter().flatten().flatten() {
+            let name = entry.file_name();
+            let name = name.to_string_lossy();
+            if name.starts_with(".confirm.") && name.ends_with(".old") {
+                let _ = fs::remove_file(entry.path());
+            }
+        }
+        let old = sibling(exe, "old");
+        if let Err(err) = fs::rename(exe, &old) {
+            let _ = fs::remove_file(&staged);
+            return Err(err).with_context(|| format!("Cannot move {} aside", exe.display()));
+        }
+    }
+    fs::rename(&staged, exe).with_context(|| format!("Cannot replace {}", exe.display()))
+}
+
+pub fn run(opts: &MainOptions, update: &SelfUpdateOptions) -> Result<()> {
+    let current = env!("CARGO_PKG_VERSION");
+    let release: Release = serde_json::from_slice(&fetch(LATEST_RELEASE)?)
+        .context("Cannot make sense of the latest release")?;
+    let latest = release.tag_name.trim_start_matches('v');
+    if version(latest) <= version(current) {
+        println!("confirm {} is up to date", current);
+        return Ok(());
+    }
+    let name = asset_name();
+    let urls = [name.as_str(), CHECKSUMS, SIGNATURE].map(|asset| release.url(asset));
+    let installable = RELEASE_KEY.is_some() && urls.iter().all(Option::is_some);
+    if update.check_only {
+        println!("confirm {} is available, this is {}", latest, current);
+        if !installable {
+            println!("It can't be installed with self-update, use cargo install");
+        }
+        return Ok(());
+    }
+    let Some(key) = RELEASE_KEY else {
+        bail!("This build has no release key to check updates with, use cargo install instead");
+    };
+    let [Some(binary_url), Some(sums_url), Some(signature_url)] = urls else {
+        bail!(
+            "Release {} doesn't publish {}, {} and {}, install it with cargo install instead",
+            release.tag_name,
+            name,
+            CHECKSUMS,
+            SIGNATURE
+        );
+    };
+    let exe = std::env::current_exe()
+        .and_then(fs::canonicalize)
+        .context("Cannot find the confirm executable")?;
+    let mut opts = opts.clone();
+    opts.prompt = format!("Update {} from {} to {}?", exe.display(), current, latest);
+    let neutral_code = opts.neutral_code;
+    let _lock = opts.take_lock()?;
+    let answer = crate::ask(opts)?;
+    if answer != Answer::Yes {
+        return finish(answer, neutral_code);
+    }
+
+    let binary = fetch(binary_url)?;
+    let sums = fetch(sums_url)?;
+    verify(key, &sums, &fetch(signature_url)?)?;
+    let sums = String::from_utf8_lossy(&sums).into_owned();
+    let expected = listed_checksum(&sums, &name)
+        .ok_or_else(|| anyhow!("{} doesn't list {}", CHECKSUMS, name))?;
+    if sha256(&binary) != expected {
+        bail!(
+            "The downloaded {} doesn't match its checksum, leaving confirm as it is",
+            name
+        );
+    }
+    replace(&exe, &binary)?;
+    println!("Updated confirm to {}", latest);
+    Ok(())
+}
@@ -0,0 +1,266 @@
+//! A self-test that runs the tool under a pseudo-terminal.
+//!
+//! Each check starts this same executable on a fresh pty, answers its prompt
+//! through the master side and looks at the exit code and what was drawn, so
+//! the report reflects how the terminal features behave on this system
+//! rather than in whatever terminal the user happens to run it from.  The
+//! report is meant to be attached to bug reports as is.
+
+use std::fs::File;
+use std::io::{IsTerminal, Read, Write};
+use std::os::fd::{AsRawFd, FromRawFd, OwnedFd};
+use std::process::{Child, Command, Stdio};
+use std::time::{Duration, Instant};
+
+use anyhow::{bail, Context, Result};
+
+use crate::diagnostics::{header, terminal, var};
+use crate::term;
+
+/// How long a check may take before the child is killed.
+const CHECK_LIMIT: Duration = Duration::from_secs(5);
+
+/// How long to wait for the prompt before answering anyway.
+const PROMPT_LIMIT: Duration = Duration::from_secs(2);
+
+/// How long to leave the child after its prompt appears, to switch the
+/// terminal mode before the answer arrives, as it would for a person.
+const SETTLE: Duration = Duration::from_millis(200);
+
+/// One way of asking, and what a working terminal should show for it.
+struct Check {
+    name: &'static str,
+    args: &'static [&'static str],
+    input: &'static [u8],
+    verify: fn(&Run) -> Result<(), String>,
+}
+
+/// What a check's child did.
+struct Run {
+    code: Option<i32>,
+    output: String,
+    /// The child's `-vv` diagnostics.
+    diagnostics: String,
+    elapsed: Duration,
+}
+
+const CHECKS: &[Check] = &[
+    Check {
+        name: "line input",
+        args: &[],
+        input: b"y\n",
+        verify: |run| answered_yes(run),
+    },
+    Check {
+        name: "single keys",
+        args: &["--no-enter"],
+        input: b"y",
+        verify: |run| {
+            answered_yes(run)?;
+            match run.output.contains("]: y") {
+                true => Err("the key was echoed, so raw mode isn't in effect".to_string()),
+                false => Ok(()),
+            }
+        },
+    },
+    Check {
+        name: "timeout",
+        args: &["--timeout", "1", "--default", "yes"],
+        input: b"",
+        verify: |run| {
+            answered_yes(run)?;
+            match run.elapsed {
+                elapsed if elapsed < Duration::from_millis(900) => {
+                    Err(format!("answered after only {:.1}s", elapsed.as_secs_f32()))
+                }
+                elapsed if elapsed > Duration::from_secs(3) => {
+                    Err(format!("took {:.1}s", elapsed.as_secs_f32()))
+                }
+                _ => Ok(()),
+            }
+        },
+    },
+    Check {
+        name: "color",
+        args: &["--highlight", "color", "--default", "yes"],
+        input: b"\n",
+        verify: |run| {
+            answered_yes(run)?;
+            match run.output.contains("\x1b[36m") {
+                true => Ok(()),
+                false => Err("no color sequence in the prompt".to_string()),
+            }
+        },
+    },
+    Check {
+        name: "redraw",
+        args: &["--icons"],
+        input: b"y\n",
+        verify: |run| {
+            answered_yes(run)?;
+            match run.output.contains("\r\x1b[J") {
+                true => Ok(()),
+                false => Err("the prompt line wasn't rewritten".to_string()),
+            }
+        },
+    },
+];
+
+fn answered_yes(run: &Run) -> Result<(), String> {
+    match run.code {
+        Some(0) => Ok(()),
+        Some(code) => Err(format!("exited with {}", code)),
+        None => Err("didn't finish in time".to_string()),
+    }
+}
+
+/// Open a pseudo-terminal of the usual size, as master and slave.
+fn open_pty() -> Result<(File, File)> {
+    let (mut master, mut slave) = (0, 0);
+    let mut size = libc::winsize {
+        ws_row: 24,
+        ws_col: 80,
+        ws_xpixel: 0,
+        ws_ypixel: 0,
+    };
+    // SAFETY: the pointers are valid for the duration of the call, and the
+    // descriptors it fills in are owned by the files made from them.
+    unsafe {
+        if libc::openpty(
+            &mut master,
+            &mut slave,
+            std::ptr::null_mut(),
+            std::ptr::null_mut(),
+            // Mutable on some platforms.
+            std::ptr::addr_of_mut!(size),
+        ) != 0
+        {
+            return Err(std::io::Error::last_os_error()).context("Cannot open a pseudo-terminal");
+        }
+        Ok((
+            File::from(OwnedFd::from_raw_fd(master)),
+            File::from(OwnedFd::from_raw_fd(slave)),
+        ))
+    }
+}
+
+/// Read whatever the child draws until `until` says to stop, the child goes
+/// away or `limit` passes.
+fn read_until(
+    master: &mut File,
+    output: &mut Vec<u8>,
+    limit: Duration,
+    until: impl Fn(&[u8]) -> bool,
+) {
+    let started = Instant::now();
+    while !until(output) {
+        let left = limit.saturating_sub(started.elapsed());
+        if left.is_zero() || !term::wait_readable(master.as_raw_fd(), left).unwrap_or(false) {
+            return;
+        }
+        let mut buf = [0; 1024];
+        match master.read(&mut buf) {
+            // Reading the master fails once the child has closed the slave.
+            Ok(0) | Err(_) => return,
+            Ok(read) => output.extend_from_slice(&buf[..read]),
+        }
+    }
+}
+
+fn finish(child: &mut Child, limit: Duration) -> Result<Option<i32>> {
+    let started = Instant::now();
+    while started.elapsed() < limit {
+        if let Some(status) = child.try_wait()? {
+            return Ok(status.code());
+        }
+        std::thread::sleep(Duration::from_millis(20));
+    }
+    let _ = child.kill();
+    child.wait()?;
+    Ok(None)
+}
+
+impl Check {
+    fn run(&self) -> Result<Run> {
+        let (mut master, slave) = open_pty()?;
+        let started = Instant::now();
+        let mut child = Command::new(std::env::current_exe()?)
+            .args(["--no-config", "-vv"])
+            .args(self.args)
+            .arg("Selftest?")
+            .env_remove("NO_COLOR")
+            .env_remove("CLICOLOR")
+            // These would answer without asking, see `environment`.
+            .env_remove("DEBIAN_FRONTEND")
+            .env_remove("NONINTERACTIVE")
+            .env_remove("ASSUME_YES")
+            .env_remove(crate::shell::ANSWER_VAR)
+            .stdin(slave.try_clone()?)
+            .stdout(slave)
+            .stderr(Stdio::piped())
+            .spawn()
+            .context("Cannot start the tool under the pseudo-terminal")?;
+
+        let mut output = Vec::new();
+        read_until(&mut master, &mut output, PROMPT_LIMIT, |out| {
+            out.windows(2).any(|pair| pair == b": ")
+        });
+        read_until(&mut master, &mut output, SETTLE, |_| false);
+        master.write_all(self.input)?;
+        read_until(&mut master, &mut output, CHECK_LIMIT, |_| false);
+        let code = finish(&mut child, CHECK_LIMIT)?;
+        let mut diagnostics = String::new();
+        if let Some(mut stderr) = child.stderr.take() {
+            stderr.read_to_string(&mut diagnostics)?;
+        }
+        Ok(Run {
+            code,
+            output: String::from_utf8_lossy(&output).into_owned(),
+            diagnostics,
+            elapsed: started.elapsed(),
+        })
+    }
+}
+
+pub fn run() -> Result<()> {
+    header();
+    println!(
+        "{}, {}, {}, {}",
+        var("TERM"),
+        var("NO_COLOR"),
+        var("LC_ALL"),
+        var("LANG")
+    );
+    println!(
+        "stdin is {}, stdout is {}",
+        terminal(std::io::stdin().is_terminal()),
+        terminal(std::io::stdout().is_terminal())
+    );
+
+    let mut failed = 0;
+    for check in CHECKS {
+        let run = match check.run() {
+            Ok(run) => run,
+            Err(err) => {
+                failed += 1;
+                println!("{:<12} FAILED: {:#}", check.name, err);
+                continue;
+            }
+        };
+        match (check.verify)(&run) {
+            Ok(()) => println!("{:<12} ok", check.name),
+            Err(err) => {
+                failed += 1;
+                println!("{:<12} FAILED: {}", check.name, err);
+                println!("    output: {:?}", run.output);
+                for line in run.diagnostics.lines() {
+                    println!("    {}", line);
+                }
+            }
+        }
+    }
+    if failed > 0 {
+        bail!("{} of {} checks failed", failed, CHECKS.len());
+    }
+    Ok(())
+}
@@ -0,0 +1,75 @@
+//! Output meant for `eval` in POSIX shells.
+
+/// The variable answers are reported in.  Answers to named questions get the
+/// question's id appended, see [`answer_var`].
+pub const ANSWER_VAR: &str = "CONFIRM_ANSWER";
+
+/// Quote `value` so the shell reads it back verbatim.
+pub fn quote(value: &str) -> String {
+    let is_safe = |ch: char| ch.is_ascii_alphanumeric() || "@%+=:,./_-".contains(ch);
+    if !value.is_empty() && value.chars().all(is_safe) {
+        return value.to_string();
+    }
+    format!("'{}'", value.replace('\'', r"'\''"))
+}
+
+/// Lists the answers confirm printed for `eval`, as `NAME=value` lines, so
+/// that once they're exported they aren't taken for answers seeded ahead of
+/// time, see `environment`.
+pub const REPORTED_VAR: &str = "CONFIRM_REPORTED";
+
+/// The variable holding the answer to the question named `id`: the id is
+/// uppercased, with anything that isn't allowed in a name replaced by an
+/// underscore.  The prefix keeps ids like `path` from clobbering `PATH`.
+pub fn answer_var(id: &str) -> String {
+    let suffix: String = id
+        .chars()
+        .map(|ch| match ch {
+            'a'..='z' | 'A'..='Z' | '0'..='9' => ch.to_ascii_uppercase(),
+            _ => '_',
+        })
+        .collect();
+    format!("{}_{}", ANSWER_VAR, suffix)
+}
+
+/// A `NAME=value` assignment line.
+pub fn assignment(name: &str, value: &str) -> String {
+    format!("{}={}", name, quote(value))
+}
+
+/// Assignment lines for `vars`, followed by one listing them in
+/// [`REPORTED_VAR`].  What earlier runs listed there is kept for the
+/// variables not assigned again, so it stays covered after another `eval`.
+pub fn report(vars: &[(String, &str)]) -> String {
+    let assigned = |name: &str| vars.iter().any(|(var, _)| var == name);
+    let previous = std::env::var(REPORTED_VAR).unwrap_or_default();
+    let kept = previous
+        .lines()
+        .filter(|line| {
+            line.split_once('=')
+                .is_some_and(|(name, _)| !assigned(name))
+        })
+        .map(str::to_string);
+    let listed: Vec<String> = kept
+        .chain(
+            vars.iter()
+                .map(|(name, value)| format!("{}={}", name, value)),
+        )
+        .collect();
+    let mut out = String::new();
+    for (name, value) in vars {
+        out.push_str(&assignment(name, value));
+        out.push('\n');
+    }
+    out.push_str(&assignment(REPORTED_VAR, &listed.join("\n")));
+    out
+}
+
+/// Whether `name` still holds the `value` confirm reported in it.
+pub fn reported(name: &str, value: &str) -> bool {
+    std::env::var(REPORTED_VAR).is_ok_and(|listed| {
+        listed
+            .lines()
+            .any(|line| line.split_once('=') == Some((name, value)))
+    })
+}
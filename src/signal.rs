@@ -0,0 +1,74 @@
+//! Signalling a waiting process with the answer, for `--signal-pid`.
+//!
+//! A daemon can start confirm and wait for a signal rather than a child's
+//! exit status, e.g. SIGUSR1 to go ahead and SIGTERM to give up.
+
+use std::fmt;
+use std::str::FromStr;
+
+use anyhow::{bail, Result};
+
+const NAMES: &[(&str, libc::c_int)] = &[
+    ("HUP", libc::SIGHUP),
+    ("INT", libc::SIGINT),
+    ("QUIT", libc::SIGQUIT),
+    ("KILL", libc::SIGKILL),
+    ("USR1", libc::SIGUSR1),
+    ("USR2", libc::SIGUSR2),
+    ("ALRM", libc::SIGALRM),
+    ("TERM", libc::SIGTERM),
+    ("CONT", libc::SIGCONT),
+    ("STOP", libc::SIGSTOP),
+    ("WINCH", libc::SIGWINCH),
+];
+
+/// A signal, by the name `kill -l` lists or by number.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub struct Signal(libc::c_int);
+
+impl FromStr for Signal {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if let Ok(number) = s.parse::<libc::c_int>() {
+            // Signal 0 only checks that the process exists.
+            return match number > 0 {
+                true => Ok(Self(number)),
+                false => Err(format!("there is no signal {}", number)),
+            };
+        }
+        let upper = s.to_ascii_uppercase();
+        let name = upper.strip_prefix("SIG").unwrap_or(&upper);
+        NAMES
+            .iter()
+            .find(|(known, _)| *known == name)
+            .map(|&(_, number)| Self(number))
+            .ok_or_else(|| format!("unknown signal {:?}, expected e.g. USR1 or TERM", s))
+    }
+}
+
+impl fmt::Display for Signal {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match NAMES.iter().find(|(_, number)| *number == self.0) {
+            Some((name, _)) => write!(f, "SIG{}", name),
+            None => write!(f, "signal {}", self.0),
+        }
+    }
+}
+
+/// Send `signal` to the process `pid`.
+pub fn send(pid: u32, signal: Signal) -> Result<()> {
+    let target = match libc::pid_t::try_from(pid) {
+        Ok(target) if target > 0 => target,
+        _ => bail!("There can't be a process {}", pid),
+    };
+    log::debug!("Sending {} to process {}", signal, pid);
+    // SAFETY: kill only takes plain values.  The pid is positive, so this
+    // can only signal that one process, never a process group: 0 and -1
+    // would signal our own group or every process we may signal.
+    if unsafe { libc::kill(target, signal.0) } != 0 {
+        let err = std::io::Error::last_os_error();
+        bail!("Cannot send {} to process {}: {}", signal, pid, err);
+    }
+    Ok(())
+}
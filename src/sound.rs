@@ -0,0 +1,160 @@
+//! Audible cues played through the system's audio player.
+//!
+//! Rather than linking an audio stack, the cue is rendered to a small WAV file
+//! in the user's runtime directory, or the temporary directory without one,
+//! and handed to whichever player the platform
+//! ships with.  The player runs in the background, so a missing or slow audio
+//! setup never delays the prompt.
+
+use std::f32::consts::TAU;
+use std::fs;
+use std::io::{self, Write};
+use std::path::PathBuf;
+use std::process::{Command, Stdio};
+
+const SAMPLE_RATE: u32 = 22_050;
+
+/// The moments a sound is played for.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum Cue {
+    /// A confirmation is being requested.
+    Prompt,
+    /// Nobody answered in time, so the default was taken.  Timeouts only
+    /// exist on Unix.
+    #[cfg(unix)]
+    Timeout,
+}
+
+impl Cue {
+    fn name(self) -> &'static str {
+        match self {
+            Cue::Prompt => "prompt",
+            #[cfg(unix)]
+            Cue::Timeout => "timeout",
+        }
+    }
+
+    /// Frequency and length of each tone in the cue.
+    fn tones(self) -> &'static [(f32, f32)] {
+        match self {
+            Cue::Prompt => &[(660.0, 0.12), (880.0, 0.18)],
+            #[cfg(unix)]
+            Cue::Timeout => &[(660.0, 0.15), (440.0, 0.3)],
+        }
+    }
+}
+
+/// Render the cue as a 16-bit mono WAV file.
+fn render_wav(cue: Cue) -> Vec<u8> {
+    let mut samples = Vec::new();
+    for &(freq, secs) in cue.tones() {
+        let count = (SAMPLE_RATE as f32 * secs) as usize;
+        for i in 0..count {
+            let t = i as f32 / SAMPLE_RATE as f32;
+            // Short linear fades keep the tone from clicking.
+            let fade = (i.min(count - i) as f32 / (SAMPLE_RATE as f32 * 0.01)).min(1.0);
+            let value = (TAU * freq * t).sin() * fade * 0.4;
+            samples.push((value * i16::MAX as f32) as i16);
+        }
+    }
+
+    let data_len = (samples.len() * 2) as u32;
+    let mut wav = Vec::with_capacity(44 + data_len as usize);
+    wav.extend_from_slice(b"RIFF");
+    wav.extend_from_slice(&(36 + data_len).to_le_bytes());
+    wav.extend_from_slice(b"WAVEfmt ");
+    wav.extend_from_slice(&16u32.to_le_bytes());
+    wav.extend_from_slice(&1u16.to_le_bytes()); // PCM
+    wav.extend_from_slice(&1u16.to_le_bytes()); // mono
+    wav.extend_from_slice(&SAMPLE_RATE.to_le_bytes());
+    wav.extend_from_slice(&(SAMPLE_RATE * 2).to_le_bytes());
+    wav.extend_from_slice(&2u16.to_le_bytes());
+    wav.extend_from_slice(&16u16.to_le_bytes());
+    wav.extend_from_slice(b"data");
+    wav.extend_from_slice(&data_len.to_le_bytes());
+    for sample in samples {
+        wav.extend_from_slice(&sample.to_le_bytes());
+    }
+    wav
+}
+
+/// Where the cue files go: `$XDG_RUNTIME_DIR` is private to the user, the
+/// temporary directory usually isn't.
+fn cue_dir() -> PathBuf {
+    match std::env::var_os("XDG_RUNTIME_DIR") {
+        Some(dir) if !dir.is_empty() => PathBuf::from(dir),
+        _ => std::env::temp_dir(),
+    }
+}
+
+/// Write the cue file, replacing it atomically so that concurrent
+/// invocations never play a half-written file.  The partial file must be
+/// new, so a file or symlink planted under its name is never written
+/// through, and renaming replaces a planted final name rather than
+/// following it.
+fn cue_file(cue: Cue) -> io::Result<PathBuf> {
+    let dir = cue_dir();
+    let path = dir.join(format!("confirm-{}.wav", cue.name()));
+    let partial = dir.join(format!(
+        "confirm-{}-{}.wav.tmp",
+        cue.name(),
+        std::process::id()
+    ));
+    let written = fs::OpenOptions::new()
+        .write(true)
+        .create_new(true)
+        .open(&partial)
+        .and_then(|mut file| file.write_all(&render_wav(cue)));
+    if let Err(err) = written.and_then(|()| fs::rename(&partial, &path)) {
+        let _ = fs::remove_file(&partial);
+        return Err(err);
+    }
+    Ok(path)
+}
+
+fn command(program: &str, args: &[&str]) -> Command {
+    let mut cmd = Command::new(program);
+    cmd.args(args);
+    cmd
+}
+
+/// The players to try, in order of preference.
+fn players(path: &str) -> Vec<Command> {
+    if cfg!(target_os = "macos") {
+        vec![command("afplay", &[path])]
+    } else if cfg!(windows) {
+        let script = format!("(New-Object Media.SoundPlayer '{}').PlaySync()", path);
+        vec![command(
+            "powershell",
+            &["-NoProfile", "-NonInteractive", "-Command", &script],
+        )]
+    } else {
+        vec![
+            command("pw-play", &[path]),
+            command("paplay", &[path]),
+            command("aplay", &["-q", path]),
+        ]
+    }
+}
+
+/// Start playing the cue in the background.
+pub fn play(cue: Cue) -> io::Result<()> {
+    let path = cue_file(cue)?;
+    let path = path.to_string_lossy();
+    for mut player in players(&path) {
+        let spawned = player
+            .stdin(Stdio::null())
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .spawn();
+        match spawned {
+            Ok(_) => return Ok(()),
+            Err(err) if err.kind() == io::ErrorKind::NotFound => continue,
+            Err(err) => return Err(err),
+        }
+    }
+    Err(io::Error::new(
+        io::ErrorKind::NotFound,
+        "no audio player found",
+    ))
+}
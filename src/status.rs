@@ -0,0 +1,105 @@
+//! A file describing the pending question, for `--status-file`.
+//!
+//! While a question is waiting for an answer the file holds a JSON object
+//! like `{"prompt": "Deploy?", "pid": 4242, "started_at": 1767225600,
+//! "attempts": 1}`, so dashboards and status bars can show that something is
+//! waiting for confirmation.  It's replaced atomically on each attempt and
+//! removed once the question is done.  A file left behind by a killed
+//! process can be recognized by its pid no longer running, and is replaced
+//! by the next run using the same path, which also sweeps up the
+//! `<name>.<pid>.tmp` files such processes were staging.
+
+use std::fs;
+use std::io;
+use std::path::PathBuf;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+#[derive(Debug)]
+pub struct StatusFile {
+    path: PathBuf,
+    prompt: String,
+    started_at: u64,
+}
+
+impl StatusFile {
+    pub fn new(path: PathBuf, prompt: &str) -> Self {
+        let started_at = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |since| since.as_secs());
+        #[cfg(unix)]
+        sweep(&path);
+        Self {
+            path,
+            prompt: prompt.to_string(),
+            started_at,
+        }
+    }
+
+    /// Record that the question is being asked for the `attempts`th time.
+    pub fn update(&self, attempts: u32) -> io::Result<()> {
+        let status = serde_json::json!({
+            "prompt": self.prompt,
+            "pid": std::process::id(),
+            "started_at": self.started_at,
+            "attempts": attempts,
+        });
+        // Staged next to it under a name of its own, so concurrent runs and
+        // sibling files are left alone.
+        let mut name = self.path.file_name().unwrap_or_default().to_os_string();
+        name.push(format!(".{}.tmp", std::process::id()));
+        let staged = self.path.with_file_name(name);
+        fs::write(&staged, format!("{}\n", status))?;
+        fs::rename(&staged, &self.path)
+    }
+
+    pub fn path(&self) -> &std::path::Path {
+        &self.path
+    }
+}
+
+/// Remove what runs that were killed before renaming it staged for `path`.
+#[cfg(unix)]
+fn sweep(path: &std::path::Path) {
+    use std::path::Path;
+
+    let (Some(dir), Some(name)) = (path.parent(), path.file_name()) else {
+        return;
+    };
+    let dir = match dir.as_os_str().is_empty() {
+        true => Path::new("."),
+        false => dir,
+    };
+    let Ok(entries) = fs::read_dir(dir) else {
+        return;
+    };
+    let prefix = format!("{}.", name.to_string_lossy());
+    for entry in entries.flatten() {
+        let file = entry.file_name();
+        let pid = (file.to_str())
+            .and_then(|file| file.strip_prefix(&prefix)?.strip_suffix(".tmp"))
+            .and_then(|pid| pid.parse::<libc::pid_t>().ok())
+            .filter(|&pid| pid > 0);
+        let Some(pid) = pid else {
+            continue;
+        };
+        // SAFETY: signal 0 only checks whether the process exists.
+        let gone = unsafe { libc::kill(pid, 0) } != 0
+            && io::Error::last_os_error().raw_os_error() == Some(libc::ESRCH);
+        if gone {
+            log::debug!("Removing {}, left by a killed run", entry.path().display());
+            let _ = fs::remove_file(entry.path());
+        }
+    }
+}
+
+impl Drop for StatusFile {
+    fn drop(&mut self) {
+        if let Err(err) = fs::remove_file(&self.path) {
+            log::debug!(
+                "Cannot remove the status file {}: {}",
+                self.path.display(),
+                err
+            );
+        }
+    }
+}
@@ -0,0 +1,178 @@
+//! A confirmation agent for the systemd password agent protocol.
+//!
+//! Questions are files named `ask.*` in the ask-password directory, and the
+//! answer is sent as a datagram to the socket named in the file.  See
+//! <https://systemd.io/PASSWORD_AGENTS/> for the full protocol.
+//!
+//! Only questions asked with echo enabled (`systemd-ask-password --echo`) are
+//! answered, since those are the ones scripts use for non-secret replies.
+//! Password requests are left alone for a real password agent.  A "yes" is
+//! sent back as the literal answer `yes`, anything else cancels the request,
+//! so the asking side sees the same success/failure split as confirm's own
+//! exit status.
+
+use std::collections::HashSet;
+use std::fs;
+use std::os::unix::net::UnixDatagram;
+use std::path::{Path, PathBuf};
+use std::thread::sleep;
+use std::time::Duration;
+
+use anyhow::{anyhow, Context, Result};
+use clap::Args;
+
+use crate::{Answer, MainOptions};
+
+const ASK_PASSWORD_DIR: &str = "/run/systemd/ask-password";
+const POLL_INTERVAL: Duration = Duration::from_millis(500);
+
+#[derive(Clone, Debug, Args)]
+pub struct AgentOptions {
+    /// Keep watching for new questions instead of exiting once the pending
+    /// ones are answered
+    #[arg(long)]
+    watch: bool,
+
+    /// Directory to look for questions in
+    #[arg(long, value_name = "PATH", default_value = ASK_PASSWORD_DIR)]
+    dir: PathBuf,
+}
+
+#[derive(Debug)]
+struct Question {
+    path: PathBuf,
+    socket: PathBuf,
+    message: String,
+    pid: Option<libc::pid_t>,
+    not_after: u64,
+    echo: bool,
+}
+
+impl Question {
+    fn parse(path: &Path, contents: &str) -> Result<Self> {
+        let mut socket = None;
+        let mut message = None;
+        let mut pid = None;
+        let mut not_after = 0;
+        let mut echo = false;
+        let mut in_ask = false;
+
+        for line in contents.lines().map(str::trim) {
+            if line.starts_with('[') {
+                in_ask = line == "[Ask]";
+                continue;
+            }
+            let (key, value) = match line.split_once('=') {
+                Some(pair) if in_ask => pair,
+                _ => continue,
+            };
+            match key.trim() {
+                "Socket" => socket = Some(PathBuf::from(value.trim())),
+                "Message" => message = Some(value.trim().to_string()),
+                "PID" => pid = value.trim().parse().ok(),
+                "NotAfter" => not_after = value.trim().parse().unwrap_or(0),
+                "Echo" => echo = value.trim() == "1",
+                _ => {}
+            }
+        }
+
+        Ok(Self {
+            path: path.to_path_buf(),
+            socket: socket.ok_or_else(|| anyhow!("{} has no Socket", path.display()))?,
+            message: message.unwrap_or_else(|| "Continue?".to_string()),
+            pid,
+            not_after,
+            echo,
+        })
+    }
+
+    /// Whether the asking process has gone away or the deadline has passed.
+    fn is_stale(&self) -> bool {
+        if self.not_after != 0 && monotonic_usec() > self.not_after {
+            return true;
+        }
+        match self.pid {
+            Some(pid) if pid > 0 => {
+                // SAFETY: signal 0 only checks whether the process exists.
+                let rc = unsafe { libc::kill(pid, 0) };
+                rc != 0 && std::io::Error::last_os_error().raw_os_error() == Some(libc::ESRCH)
+            }
+            _ => false,
+        }
+    }
+
+    fn reply(&self, confirmed: bool) -> Result<()> {
+        let payload: &[u8] = if confirmed { b"+yes" } else { b"-" };
+        UnixDatagram::unbound()?
+            .send_to(payload, &self.socket)
+            .with_context(|| format!("Cannot answer on {}", self.socket.display()))?;
+        Ok(())
+    }
+}
+
+fn monotonic_usec() -> u64 {
+    let mut ts = libc::timespec {
+        tv_sec: 0,
+        tv_nsec: 0,
+    };
+    // SAFETY: `ts` is a valid timespec for the duration of the call.
+    unsafe { libc::clock_gettime(libc::CLOCK_MONOTONIC, &mut ts) };
+    ts.tv_sec as u64 * 1_000_000 + ts.tv_nsec as u64 / 1_000
+}
+
+fn pending(dir: &Path) -> Result<Vec<Question>> {
+    let mut paths = Vec::new();
+    for entry in fs::read_dir(dir).with_context(|| format!("Cannot read {}", dir.display()))? {
+        let path = entry?.path();
+        let is_ask = path
+            .file_name()
+            .and_then(|name| name.to_str())
+            .is_some_and(|name| name.starts_with("ask."));
+        if is_ask {
+            paths.push(path);
+        }
+    }
+    paths.sort();
+
+    let mut questions = Vec::new();
+    for path in paths {
+        // Questions are removed as soon as they are answered elsewhere.
+        let contents = match fs::read_to_string(&path) {
+            Ok(contents) => contents,
+            Err(_) => continue,
+        };
+        match Question::parse(&path, &contents) {
+            Ok(question) => questions.push(question),
+            Err(err) => eprintln!("Skipping malformed question: {}", err),
+        }
+    }
+    Ok(questions)
+}
+
+pub fn run(opts: &MainOptions, agent: &AgentOptions) -> Result<()> {
+    let mut handled = HashSet::new();
+    loop {
+        let questions = pending(&agent.dir)?;
+        handled.retain(|path| questions.iter().any(|q: &Question| &q.path == path));
+
+        for question in questions {
+            if !question.echo || question.is_stale() || handled.contains(&question.path) {
+                continue;
+            }
+            let mut opts = opts.clone();
+            opts.prompt = question.message.clone();
+            // Like a plain prompt: presets, the precheck and the audit log
+            // all apply.
+            let confirmed = crate::ask(opts)? == Answer::Yes;
+            if question.path.exists() && !question.is_stale() {
+                question.reply(confirmed)?;
+            }
+            handled.insert(question.path);
+        }
+
+        if !agent.watch {
+            return Ok(());
+        }
+        sleep(POLL_INTERVAL);
+    }
+}
@@ -0,0 +1,419 @@
+//! Terminal access: where answers come from and where prompts go.
+
+use std::fmt;
+#[cfg(unix)]
+use std::fs::{File, OpenOptions};
+use std::io::{self, stderr, stdin, stdout, IsTerminal, Write};
+#[cfg(unix)]
+use std::io::{BufRead, BufReader, Read};
+#[cfg(unix)]
+use std::os::fd::{AsRawFd, RawFd};
+#[cfg(unix)]
+use std::path::Path;
+use std::process::Stdio;
+#[cfg(unix)]
+use std::time::Duration;
+
+#[cfg(unix)]
+use anyhow::{anyhow, Result};
+
+use confirm_rs::events::EventStream;
+
+#[cfg(unix)]
+use crate::fifo::Fifo;
+
+/// Where answers are read from.
+#[derive(Debug)]
+pub enum AnswerSource {
+    Stdin,
+    /// An inherited file descriptor, typically a pipe from a supervising process.
+    #[cfg(unix)]
+    Fd(BufReader<File>),
+    /// A terminal device opened by path.
+    #[cfg(unix)]
+    Tty(BufReader<File>),
+}
+
+impl AnswerSource {
+    pub fn read_line(&mut self, buf: &mut String) -> io::Result<usize> {
+        let start = buf.len();
+        let read = match self {
+            AnswerSource::Stdin => stdin().read_line(buf),
+            #[cfg(unix)]
+            AnswerSource::Fd(reader) | AnswerSource::Tty(reader) => reader.read_line(buf),
+        }?;
+        log::trace!("Read line {:?}", &buf[start..]);
+        Ok(read)
+    }
+
+    /// Read a single key, returning 0 at end of input.  On unix the source
+    /// should be in raw mode first, see [`RawMode`].
+    pub fn read_key(&mut self) -> io::Result<u8> {
+        let byte = match self {
+            #[cfg(unix)]
+            AnswerSource::Stdin => read_byte(&mut stdin()),
+            #[cfg(not(unix))]
+            AnswerSource::Stdin => getch::Getch::new().getch(),
+            #[cfg(unix)]
+            AnswerSource::Fd(reader) | AnswerSource::Tty(reader) => read_byte(reader),
+        }?;
+        log::trace!("Read byte {:#04x}", byte);
+        Ok(byte)
+    }
+
+    /// Read the next key, decoding escape sequences and UTF-8.  The source
+    /// should be in raw mode first, see [`RawMode`].
+    #[cfg(unix)]
+    pub fn read_key_event(&mut self) -> io::Result<Key> {
+        let first = self.read_key()?;
+        let key = match first {
+            0 | 4 => Key::Eof,
+            3 => Key::Interrupt,
+            b'\r' | b'\n' => Key::Enter,
+            8 | 127 => Key::Backspace,
+            0x1b => self.read_escape()?,
+            byte if byte < 0x80 => Key::Char(byte as char),
+            lead => {
+                let len = match lead {
+                    0xc0..=0xdf => 2,
+                    0xe0..=0xef => 3,
+                    _ => 4,
+                };
+                let mut bytes = vec![lead];
+                for _ in 1..len {
+                    bytes.push(self.read_key()?);
+                }
+                match std::str::from_utf8(&bytes) {
+                    Ok(text) => text.chars().next().map_or(Key::Other, Key::Char),
+                    Err(_) => Key::Other,
+                }
+            }
+        };
+        Ok(key)
+    }
+
+    /// Decode what follows an escape byte.  A lone escape is only told apart
+    /// from the start of a sequence by nothing else arriving shortly after.
+    #[cfg(unix)]
+    fn read_escape(&mut self) -> io::Result<Key> {
+        let follows = |source: &mut Self| -> io::Result<bool> {
+            Ok(source.has_buffered() || wait_readable(source.as_raw_fd(), ESCAPE_DELAY)?)
+        };
+        if !follows(self)? {
+            return Ok(Key::Escape);
+        }
+        let intro = self.read_key()?;
+        if intro != b'[' && intro != b'O' {
+            return Ok(Key::Other);
+        }
+        let mut params = Vec::new();
+        loop {
+            let byte = self.read_key()?;
+            match byte {
+                b'0'..=b'9' | b';' => params.push(byte),
+                b'A' => return Ok(Key::Up),
+                b'B' => return Ok(Key::Down),
+                b'~' => {
+                    return Ok(match params.as_slice() {
+                        b"5" => Key::PageUp,
+                        b"6" => Key::PageDown,
+                        _ => Key::Other,
+                    })
+                }
+                0 => return Ok(Key::Eof),
+                _ => return Ok(Key::Other),
+            }
+        }
+    }
+
+    /// Whether input has already been read off the descriptor and is waiting
+    /// in our buffer, where polling the descriptor wouldn't see it.
+    #[cfg(unix)]
+    pub fn has_buffered(&self) -> bool {
+        match self {
+            AnswerSource::Stdin => false,
+            AnswerSource::Fd(reader) | AnswerSource::Tty(reader) => !reader.buffer().is_empty(),
+        }
+    }
+}
+
+impl fmt::Display for AnswerSource {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            AnswerSource::Stdin => write!(f, "stdin"),
+            #[cfg(unix)]
+            AnswerSource::Fd(reader) => write!(f, "descriptor {}", reader.get_ref().as_raw_fd()),
+            #[cfg(unix)]
+            AnswerSource::Tty(_) => write!(f, "the terminal device"),
+        }
+    }
+}
+
+/// How long to wait for the rest of an escape sequence.
+#[cfg(unix)]
+const ESCAPE_DELAY: Duration = Duration::from_millis(50);
+
+/// A decoded keypress.
+#[cfg(unix)]
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum Key {
+    Char(char),
+    Enter,
+    Backspace,
+    Escape,
+    Up,
+    Down,
+    PageUp,
+    PageDown,
+    /// Ctrl-C, when signals are disabled.
+    Interrupt,
+    /// End of input, or Ctrl-D.
+    Eof,
+    /// Anything we don't handle, like function keys.
+    Other,
+}
+
+#[cfg(unix)]
+impl AsRawFd for AnswerSource {
+    fn as_raw_fd(&self) -> RawFd {
+        match self {
+            AnswerSource::Stdin => stdin().as_raw_fd(),
+            AnswerSource::Fd(reader) | AnswerSource::Tty(reader) => reader.get_ref().as_raw_fd(),
+        }
+    }
+}
+
+#[cfg(unix)]
+fn read_byte(reader: &mut impl Read) -> io::Result<u8> {
+    let mut byte = [0];
+    Ok(match reader.read(&mut byte)? {
+        0 => 0,
+        _ => byte[0],
+    })
+}
+
+/// Where the prompt is displayed.
+#[derive(Debug)]
+pub enum PromptSink {
+    Stdout,
+    /// Used when stdout carries something else, like `--machine` events.
+    Stderr,
+    #[cfg(unix)]
+    Tty(File),
+}
+
+impl Write for PromptSink {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        match self {
+            PromptSink::Stdout => stdout().write(buf),
+            PromptSink::Stderr => stderr().write(buf),
+            #[cfg(unix)]
+            PromptSink::Tty(file) => file.write(buf),
+        }
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        match self {
+            PromptSink::Stdout => stdout().flush(),
+            PromptSink::Stderr => stderr().flush(),
+            #[cfg(unix)]
+            PromptSink::Tty(file) => file.flush(),
+        }
+    }
+}
+
+impl PromptSink {
+    /// Somewhere for a child process, like a pager, to write to the same
+    /// place.
+    pub fn stdio(&self) -> io::Result<Stdio> {
+        Ok(match self {
+            PromptSink::Stdout => Stdio::inherit(),
+            PromptSink::Stderr => Stdio::from(stderr()),
+            #[cfg(unix)]
+            PromptSink::Tty(file) => Stdio::from(file.try_clone()?),
+        })
+    }
+
+    pub fn is_terminal(&self) -> bool {
+        match self {
+            PromptSink::Stdout => stdout().is_terminal(),
+            PromptSink::Stderr => stderr().is_terminal(),
+            #[cfg(unix)]
+            PromptSink::Tty(file) => file.is_terminal(),
+        }
+    }
+
+    /// The width of the terminal, if it is one.
+    #[cfg(unix)]
+    pub fn columns(&self) -> Option<usize> {
+        self.size().map(|size| size.ws_col.into())
+    }
+
+    /// The height of the terminal, if it is one.
+    #[cfg(unix)]
+    pub fn rows(&self) -> Option<usize> {
+        self.size()
+            .filter(|size| size.ws_row > 0)
+            .map(|size| size.ws_row.into())
+    }
+
+    #[cfg(unix)]
+    fn size(&self) -> Option<libc::winsize> {
+        let fd = match self {
+            PromptSink::Stdout => libc::STDOUT_FILENO,
+            PromptSink::Stderr => libc::STDERR_FILENO,
+            PromptSink::Tty(file) => file.as_raw_fd(),
+        };
+        let mut size = libc::winsize {
+            ws_row: 0,
+            ws_col: 0,
+            ws_xpixel: 0,
+            ws_ypixel: 0,
+        };
+        // SAFETY: TIOCGWINSZ only writes a `winsize` through the pointer.
+        match unsafe { libc::ioctl(fd, libc::TIOCGWINSZ, &mut size) } {
+            0 if size.ws_col > 0 => Some(size),
+            _ => None,
+        }
+    }
+
+    #[cfg(not(unix))]
+    pub fn columns(&self) -> Option<usize> {
+        None
+    }
+
+    #[cfg(not(unix))]
+    pub fn rows(&self) -> Option<usize> {
+        None
+    }
+}
+
+impl fmt::Display for PromptSink {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            PromptSink::Stdout => write!(f, "stdout"),
+            PromptSink::Stderr => write!(f, "stderr"),
+            #[cfg(unix)]
+            PromptSink::Tty(_) => write!(f, "the terminal device"),
+        }
+    }
+}
+
+/// The input and output a prompt is connected to.
+#[derive(Debug)]
+pub struct Terminal {
+    pub source: AnswerSource,
+    pub sink: PromptSink,
+    pub events: Option<EventStream>,
+    /// Another way to answer, from outside the terminal.
+    #[cfg(unix)]
+    pub fifo: Option<Fifo>,
+}
+
+#[cfg(unix)]
+pub fn open_tty(path: &Path) -> Result<File> {
+    OpenOptions::new()
+        .read(true)
+        .write(true)
+        .open(path)
+        .map_err(|err| anyhow!("Cannot open terminal {}: {}", path.display(), err))
+}
+
+#[cfg(unix)]
+pub fn open_answer_fd(fd: i32) -> Result<File> {
+    use std::os::fd::BorrowedFd;
+
+    // SAFETY: the descriptor is only borrowed long enough to duplicate it, and
+    // clap guarantees it is non-negative.  A descriptor that isn't open makes
+    // the duplication fail instead of being used.
+    let borrowed = unsafe { BorrowedFd::borrow_raw(fd) };
+    let owned = borrowed
+        .try_clone_to_owned()
+        .map_err(|err| anyhow!("Cannot use file descriptor {} for answers: {}", fd, err))?;
+    Ok(File::from(owned))
+}
+
+#[cfg(unix)]
+pub fn is_terminal(fd: RawFd) -> bool {
+    // SAFETY: isatty only inspects the descriptor.
+    unsafe { libc::isatty(fd) == 1 }
+}
+
+/// Wait up to `timeout` for `fd` to become readable.
+#[cfg(unix)]
+pub fn wait_readable(fd: RawFd, timeout: Duration) -> io::Result<bool> {
+    Ok(wait_any(&[fd], Some(timeout))?.is_some())
+}
+
+/// Wait up to `timeout`, or forever without one, for any of `fds` to become
+/// readable, returning the index of the first that did.
+#[cfg(unix)]
+pub fn wait_any(fds: &[RawFd], timeout: Option<Duration>) -> io::Result<Option<usize>> {
+    let mut pollfds: Vec<_> = fds
+        .iter()
+        .map(|&fd| libc::pollfd {
+            fd,
+            events: libc::POLLIN,
+            revents: 0,
+        })
+        .collect();
+    let millis = timeout.map_or(-1, |timeout| {
+        timeout.as_millis().min(libc::c_int::MAX as u128) as libc::c_int
+    });
+    loop {
+        // SAFETY: `pollfds` holds as many valid entries as are passed, for
+        // the duration of the call.
+        match unsafe { libc::poll(pollfds.as_mut_ptr(), pollfds.len() as libc::nfds_t, millis) } {
+            -1 => {
+                let err = io::Error::last_os_error();
+                if err.kind() != io::ErrorKind::Interrupted {
+                    return Err(err);
+                }
+            }
+            0 => return Ok(None),
+            _ => return Ok(pollfds.iter().position(|pollfd| pollfd.revents != 0)),
+        }
+    }
+}
+
+/// Keeps a terminal in non-canonical, non-echoing mode until dropped.
+#[cfg(unix)]
+pub struct RawMode {
+    fd: RawFd,
+    original: termios::Termios,
+}
+
+#[cfg(unix)]
+impl RawMode {
+    /// Switch `fd` to raw mode, or do nothing if it isn't a terminal.
+    pub fn enable(fd: RawFd) -> io::Result<Option<Self>> {
+        Self::clear_flags(fd, termios::ICANON | termios::ECHO)
+    }
+
+    /// Like [`RawMode::enable`], but Ctrl-C is read as a key instead of
+    /// raising SIGINT, so the terminal can be restored before exiting.
+    pub fn enable_without_signals(fd: RawFd) -> io::Result<Option<Self>> {
+        Self::clear_flags(fd, termios::ICANON | termios::ECHO | termios::ISIG)
+    }
+
+    fn clear_flags(fd: RawFd, flags: termios::tcflag_t) -> io::Result<Option<Self>> {
+        use termios::{tcsetattr, Termios, TCSADRAIN};
+
+        let original = match Termios::from_fd(fd) {
+            Ok(original) => original,
+            Err(_) if !is_terminal(fd) => return Ok(None),
+            Err(err) => return Err(err),
+        };
+        let mut raw = original;
+        raw.c_lflag &= !flags;
+        tcsetattr(fd, TCSADRAIN, &raw)?;
+        Ok(Some(Self { fd, original }))
+    }
+}
+
+#[cfg(unix)]
+impl Drop for RawMode {
+    fn drop(&mut self) {
+        let _ = termios::tcsetattr(self.fd, termios::TCSADRAIN, &self.original);
+    }
+}
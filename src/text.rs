@@ -0,0 +1,116 @@
+//! Measuring and arranging text for the terminal.
+
+use unicode_width::UnicodeWidthChar;
+
+/// How many columns `text` takes up on a terminal.  Wide characters like
+/// CJK take two, combining marks none, and escape sequences like the ones
+/// from [`Highlight`](crate::Highlight) aren't counted.
+pub fn display_width(text: &str) -> usize {
+    let mut width = 0;
+    let mut chars = text.chars();
+    while let Some(ch) = chars.next() {
+        if ch == '\x1b' {
+            // Skip a CSI sequence up to its final byte.
+            if chars.next() == Some('[') {
+                for ch in chars.by_ref() {
+                    if ('\x40'..='\x7e').contains(&ch) {
+                        break;
+                    }
+                }
+            }
+            continue;
+        }
+        width += ch.width().unwrap_or(0);
+    }
+    width
+}
+
+/// How many terminal rows are taken up by `width` columns of text, with the
+/// cursor on the last one.
+pub fn rows(width: usize, columns: usize) -> usize {
+    match columns {
+        0 => 1,
+        columns => width / columns + 1,
+    }
+}
+
+/// Whether `text` contains right-to-left letters, like Hebrew or Arabic.
+fn has_rtl(text: &str) -> bool {
+    text.chars().any(|ch| {
+        matches!(ch,
+            '\u{0590}'..='\u{08FF}'
+            | '\u{FB1D}'..='\u{FDFF}'
+            | '\u{FE70}'..='\u{FEFF}'
+            | '\u{10800}'..='\u{10FFF}'
+            | '\u{1E800}'..='\u{1EFFF}')
+    })
+}
+
+/// Keep right-to-left `text` from reordering what's written after it, like
+/// the hint box, by wrapping it in a Unicode directional isolate.
+pub fn isolate(text: &str) -> String {
+    match has_rtl(text) {
+        true => format!("\u{2068}{}\u{2069}", text),
+        false => text.to_string(),
+    }
+}
+
+/// Make untrusted `text`, like a file name or commit message put into a
+/// prompt, safe to write to a terminal: escape sequences are dropped, other
+/// control characters shown in caret notation like "^M", and bidirectional
+/// overrides, which could reorder the hint after the text, removed.
+/// Newlines and tabs are kept.
+pub fn sanitize(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut chars = text.chars().peekable();
+    while let Some(ch) = chars.next() {
+        let introducer = match ch {
+            '\x1b' => match chars.next() {
+                Some(next @ ('[' | ']' | 'P' | 'X' | '^' | '_')) => next,
+                // Any other escape is a single character long.
+                _ => continue,
+            },
+            '\u{9b}' => '[',
+            '\u{9d}' => ']',
+            '\u{90}' | '\u{98}' | '\u{9e}' | '\u{9f}' => 'P',
+            '\n' | '\t' => {
+                out.push(ch);
+                continue;
+            }
+            '\x00'..='\x1f' | '\x7f' => {
+                out.push('^');
+                out.push(char::from(ch as u8 ^ 0x40));
+                continue;
+            }
+            '\u{80}'..='\u{9f}' | '\u{202a}'..='\u{202e}' | '\u{2066}'..='\u{2069}' => continue,
+            _ => {
+                out.push(ch);
+                continue;
+            }
+        };
+        match introducer {
+            // A control sequence, up to its final byte.
+            '[' => {
+                for ch in chars.by_ref() {
+                    if ('\x40'..='\x7e').contains(&ch) {
+                        break;
+                    }
+                }
+            }
+            // A string like an OSC title, up to BEL or the string terminator.
+            _ => {
+                while let Some(ch) = chars.next() {
+                    match ch {
+                        '\x07' | '\u{9c}' => break,
+                        '\x1b' if chars.peek() == Some(&'\\') => {
+                            chars.next();
+                            break;
+                        }
+                        _ => {}
+                    }
+                }
+            }
+        }
+    }
+    out
+}
@@ -0,0 +1,55 @@
+//! Running the `--validator` command on each answer.
+//!
+//! The command runs through the shell with the answer on stdin and in
+//! `CONFIRM_INPUT`.  If the first line it prints is "yes", "no" or "retry",
+//! that's the answer; otherwise exiting 0 means yes, 1 no, and anything else
+//! asks again.  Its stderr goes to the terminal, so it can say why an answer
+//! was rejected.
+
+use std::io::Write;
+use std::process::Stdio;
+
+use anyhow::{Context, Result};
+use confirm_rs::{Answer, Validator};
+
+use crate::precheck::shell;
+
+fn run(command: &str, input: &str) -> Result<Answer> {
+    let mut child = shell(command)
+        .env("CONFIRM_INPUT", input)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .spawn()
+        .with_context(|| format!("Cannot run the validator {:?}", command))?;
+    if let Some(mut stdin) = child.stdin.take() {
+        // The validator may not read its input, e.g. if it uses the variable.
+        let _ = writeln!(stdin, "{}", input);
+    }
+    let output = child.wait_with_output()?;
+    log::debug!("The validator {:?} exited with {}", command, output.status);
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let said = stdout.lines().next().unwrap_or_default().trim();
+    Ok(match said.to_ascii_lowercase().as_str() {
+        "yes" => Answer::Yes,
+        "no" => Answer::No,
+        "retry" => Answer::Retry,
+        _ => match output.status.code() {
+            Some(0) => Answer::Yes,
+            Some(1) => Answer::No,
+            _ => Answer::Retry,
+        },
+    })
+}
+
+/// A validator asking `command` about each answer.  Failing to run it asks
+/// again, after saying why unless `quiet`.
+pub fn command(command: String, quiet: bool) -> Validator {
+    Validator::new(move |input| {
+        Some(run(&command, input).unwrap_or_else(|err| {
+            if !quiet {
+                eprintln!("{:#}", err);
+            }
+            Answer::Retry
+        }))
+    })
+}
@@ -0,0 +1,548 @@
+//! Multi-step question flows described in a TOML or YAML file.
+//!
+//! A definition is a list of steps, each a yes/no question, a choice from a
+//! list, or free text.  Steps can be made conditional on earlier answers with
+//! `when`, which takes one or more terms joined by `&&`:
+//!
+//! * `id` holds when that step was answered yes (or with non-empty text),
+//! * `!id` holds when it wasn't, or was skipped,
+//! * `id == value` and `id != value` compare the answer as text.
+//!
+//! ```toml
+//! [[step]]
+//! id = "database"
+//! prompt = "Install a database?"
+//! default = "yes"
+//!
+//! [[step]]
+//! id = "engine"
+//! type = "choose"
+//! prompt = "Which engine?"
+//! options = ["postgres", "sqlite"]
+//! when = "database"
+//! ```
+//!
+//! Choose steps asked on a terminal list their options for typing to filter
+//! them, see [`picker`](crate::picker).
+//!
+//! Choose and input steps with `history = true` remember their answers across
+//! runs.  On a terminal, the up and down arrows recall them, and choose
+//! steps start with the last choice selected in the list instead.
+//!
+//! Prompts are shown on stderr (or the `--tty` device) and the answers of the
+//! steps that were asked are printed on stdout once the flow completes.
+
+use std::fs;
+use std::io::Write;
+#[cfg(unix)]
+use std::os::fd::AsRawFd;
+use std::path::{Path, PathBuf};
+
+use anyhow::{anyhow, bail, Context, Result};
+use clap::{Args, ValueEnum};
+use confirm_rs::{text, Decision};
+use serde::ser::SerializeMap;
+use serde::{Deserialize, Serialize, Serializer};
+
+use crate::history::History;
+use crate::term::{PromptSink, Terminal};
+use crate::{shell, Answer, MainOptions};
+
+#[derive(Clone, Debug, Args)]
+pub struct WizardOptions {
+    /// The flow definition, in TOML or YAML (by extension, TOML otherwise)
+    file: PathBuf,
+
+    /// How to print the collected answers
+    #[arg(long, value_enum, default_value_t = Format::Json)]
+    output: Format,
+}
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq, ValueEnum)]
+enum Format {
+    /// A JSON object keyed by step id
+    Json,
+    /// One `CONFIRM_ANSWER_<ID>=value` assignment per step, for `eval`,
+    /// listed in `CONFIRM_REPORTED` so they're never read back as answers
+    Shell,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct Definition {
+    #[serde(rename = "step", alias = "steps")]
+    steps: Vec<Step>,
+}
+
+#[derive(Debug, Copy, Clone, Default, Eq, PartialEq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+enum StepKind {
+    #[default]
+    Confirm,
+    Choose,
+    Input,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct Step {
+    id: String,
+    prompt: String,
+    #[serde(rename = "type", default)]
+    kind: StepKind,
+    /// Used on an empty answer.  For confirm steps, "yes" or "no".
+    default: Option<String>,
+    /// The choices of a choose step.
+    #[serde(default)]
+    options: Vec<String>,
+    /// For input steps, ask again on an empty answer with no default.
+    #[serde(default)]
+    required: bool,
+    /// Remember answers across runs, for recall with the arrow keys.
+    #[serde(default)]
+    history: bool,
+    when: Option<String>,
+}
+
+impl Definition {
+    fn load(path: &Path) -> Result<Self> {
+        let text =
+            fs::read_to_string(path).with_context(|| format!("Cannot read {}", path.display()))?;
+        let is_yaml = matches!(
+            path.extension().and_then(|ext| ext.to_str()),
+            Some("yaml" | "yml")
+        );
+        let definition: Self = match is_yaml {
+            true => serde_yaml::from_str(&text)
+                .with_context(|| format!("Invalid wizard definition {}", path.display()))?,
+            false => toml::from_str(&text)
+                .with_context(|| format!("Invalid wizard definition {}", path.display()))?,
+        };
+        definition.validate()?;
+        Ok(definition)
+    }
+
+    fn validate(&self) -> Result<()> {
+        let mut seen: Vec<&str> = Vec::new();
+        for step in &self.steps {
+            if seen.contains(&step.id.as_str()) {
+                bail!("Duplicate step id {:?}", step.id);
+            }
+            match step.kind {
+                StepKind::Confirm => {
+                    if let Some(default) = &step.default {
+                        parse_confirm_default(default)
+                            .with_context(|| format!("In step {:?}", step.id))?;
+                    }
+                }
+                StepKind::Choose => {
+                    if step.options.is_empty() {
+                        bail!("Step {:?} has no options to choose from", step.id);
+                    }
+                    if let Some(default) = &step.default {
+                        if !step.options.contains(default) {
+                            bail!("Default of step {:?} isn't one of its options", step.id);
+                        }
+                    }
+                }
+                StepKind::Input => {}
+            }
+            if let Some(when) = &step.when {
+                for term in terms(when) {
+                    if !seen.contains(&term.id) {
+                        bail!(
+                            "Step {:?} depends on {:?}, which isn't an earlier step",
+                            step.id,
+                            term.id
+                        );
+                    }
+                }
+            }
+            seen.push(&step.id);
+        }
+        Ok(())
+    }
+}
+
+fn parse_confirm_default(default: &str) -> Result<Answer> {
+    match Answer::from_str(default, true) {
+        Ok(answer) => Ok(answer),
+        Err(_) => Err(anyhow!(
+            "Default must be yes, no or retry, found: {}",
+            default
+        )),
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Value {
+    Bool(bool),
+    Text(String),
+}
+
+impl Value {
+    fn is_truthy(&self) -> bool {
+        match self {
+            Value::Bool(value) => *value,
+            Value::Text(text) => !text.is_empty(),
+        }
+    }
+
+    fn as_text(&self) -> &str {
+        match self {
+            Value::Bool(true) => "yes",
+            Value::Bool(false) => "no",
+            Value::Text(text) => text,
+        }
+    }
+}
+
+impl Serialize for Value {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match self {
+            Value::Bool(value) => serializer.serialize_bool(*value),
+            Value::Text(text) => serializer.serialize_str(text),
+        }
+    }
+}
+
+/// Answers in the order they were given.
+#[derive(Debug, Default)]
+struct Answers(Vec<(String, Value)>);
+
+impl Answers {
+    fn get(&self, id: &str) -> Option<&Value> {
+        self.0
+            .iter()
+            .find(|(key, _)| key == id)
+            .map(|(_, value)| value)
+    }
+}
+
+impl Serialize for Answers {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut map = serializer.serialize_map(Some(self.0.len()))?;
+        for (id, value) in &self.0 {
+            map.serialize_entry(id, value)?;
+        }
+        map.end()
+    }
+}
+
+#[derive(Debug)]
+enum Test<'a> {
+    Truthy,
+    Falsy,
+    Equals(&'a str),
+    NotEquals(&'a str),
+}
+
+#[derive(Debug)]
+struct Term<'a> {
+    id: &'a str,
+    test: Test<'a>,
+}
+
+fn terms(condition: &str) -> impl Iterator<Item = Term<'_>> {
+    condition.split("&&").map(|term| {
+        let term = term.trim();
+        if let Some((id, value)) = term.split_once("!=") {
+            Term {
+                id: id.trim(),
+                test: Test::NotEquals(value.trim()),
+            }
+        } else if let Some((id, value)) = term.split_once("==") {
+            Term {
+                id: id.trim(),
+                test: Test::Equals(value.trim()),
+            }
+        } else if let Some(id) = term.strip_prefix('!') {
+            Term {
+                id: id.trim(),
+                test: Test::Falsy,
+            }
+        } else {
+            Term {
+                id: term,
+                test: Test::Truthy,
+            }
+        }
+    })
+}
+
+fn holds(condition: &str, answers: &Answers) -> bool {
+    terms(condition).all(|term| {
+        let value = answers.get(term.id);
+        let text = value.map_or("", Value::as_text);
+        match term.test {
+            Test::Truthy => value.is_some_and(Value::is_truthy),
+            Test::Falsy => !value.is_some_and(Value::is_truthy),
+            Test::Equals(expected) => text == expected,
+            Test::NotEquals(expected) => text != expected,
+        }
+    })
+}
+
+/// Runs the choose and input steps on the shared terminal, and other
+/// questions that aren't yes or no.
+pub struct Asker<'a> {
+    pub opts: &'a MainOptions,
+    pub terminal: Terminal,
+}
+
+impl Asker<'_> {
+    /// Print a diagnostic, unless asked to be quiet.
+    fn warn(&self, message: &str) {
+        if !self.opts.quiet {
+            eprintln!("{}", self.opts.render().display(message));
+        }
+    }
+
+    fn remember(&self, history: Option<&mut History>, answer: &str) {
+        if let Some(history) = history {
+            if let Err(err) = history.record(answer) {
+                self.warn(&format!("Cannot save answer history: {}", err));
+            }
+        }
+    }
+
+    fn read_line(&mut self, prompt: &str, history: Option<&History>) -> Result<String> {
+        let render = self.opts.render();
+        let prompt = render.display(prompt);
+        write!(self.terminal.sink, "{}", prompt)?;
+        self.terminal.sink.flush()?;
+
+        #[cfg(unix)]
+        if let Some(history) = history {
+            let fd = self.terminal.source.as_raw_fd();
+            if !render.accessible && crate::term::is_terminal(fd) && !confirm_rs::dumb_terminal() {
+                return match crate::editor::read_line(
+                    &mut self.terminal.source,
+                    &mut self.terminal.sink,
+                    &prompt,
+                    history.entries(),
+                )? {
+                    Some(line) => Ok(line.trim().to_string()),
+                    None => bail!("Input closed.  Aborting..."),
+                };
+            }
+        }
+        #[cfg(not(unix))]
+        let _ = history;
+
+        let mut line = String::new();
+        if self.terminal.source.read_line(&mut line)? == 0 {
+            writeln!(self.terminal.sink)?;
+            bail!("Input closed.  Aborting...");
+        }
+        Ok(line.trim().to_string())
+    }
+
+    /// Ask until `parse` accepts the answer, giving up after as many attempts
+    /// as a confirmation would.
+    pub fn ask_until<T>(
+        &mut self,
+        prompt: &str,
+        history: Option<&History>,
+        mut parse: impl FnMut(&str) -> Result<T, String>,
+    ) -> Result<T> {
+        let total = self.opts.try_mode().total_asks();
+        let mut attempt = 1;
+        loop {
+            let line = self.read_line(prompt, history)?;
+            match parse(&line) {
+                Ok(value) => return Ok(value),
+                Err(msg) => self.warn(&msg),
+            }
+            if total == Some(attempt) {
+                bail!("Retry count exceeded.  Aborting...");
+            }
+            attempt += 1;
+        }
+    }
+
+    /// Whether the terminal can take the type-to-filter list.
+    #[cfg(unix)]
+    fn can_filter(&self) -> bool {
+        let fd = self.terminal.source.as_raw_fd();
+        !self.opts.render().accessible
+            && crate::term::is_terminal(fd)
+            && self.terminal.sink.is_terminal()
+            && !confirm_rs::dumb_terminal()
+    }
+
+    /// List `options` under `prompt` and ask for one of them: by typing to
+    /// filter the list on a terminal, by number or by name otherwise.
+    /// Returns the index of the option picked.
+    pub fn pick(
+        &mut self,
+        prompt: &str,
+        options: &[String],
+        default: Option<&str>,
+        history: Option<&History>,
+    ) -> Result<usize> {
+        let default = default.and_then(|default| options.iter().position(|opt| opt == default));
+        #[cfg(unix)]
+        if self.can_filter() {
+            let render = self.opts.render();
+            let heading = text::isolate(&text::sanitize(prompt));
+            writeln!(self.terminal.sink, "{}", render.display(&heading))?;
+            let shown: Vec<String> = options
+                .iter()
+                .map(|option| render.display(&text::sanitize(option)))
+                .collect();
+            let marker = render.display("\u{203A}");
+            // The arrows move through the list here, so the history is used
+            // to start at the last choice instead.
+            let last = history.and_then(|history| {
+                let entries = history.entries().iter().rev();
+                entries
+                    .filter_map(|entry| options.iter().position(|opt| opt == entry))
+                    .next()
+            });
+            return match crate::picker::pick(
+                &mut self.terminal.source,
+                &mut self.terminal.sink,
+                &shown,
+                last.or(default),
+                &marker,
+            )? {
+                Some(i) => Ok(i),
+                None => bail!("{}", render.catalog().cancelled),
+            };
+        }
+        let mut listing = format!("{}\n", text::isolate(&text::sanitize(prompt)));
+        for (i, option) in options.iter().enumerate() {
+            listing.push_str(&format!(
+                "  {}) {}\n",
+                i + 1,
+                text::isolate(&text::sanitize(option))
+            ));
+        }
+        let listing = self.opts.render().display(&listing);
+        write!(self.terminal.sink, "{}", listing)?;
+
+        let count = options.len();
+        let prompt = match default {
+            Some(i) => format!("Choose 1-{} [{}]: ", count, i + 1),
+            None => format!("Choose 1-{}: ", count),
+        };
+        self.ask_until(&prompt, history, |line| {
+            if line.is_empty() {
+                return default.ok_or_else(|| "Please choose one of the options".to_string());
+            }
+            if let Ok(n) = line.parse::<usize>() {
+                if (1..=count).contains(&n) {
+                    return Ok(n - 1);
+                }
+            }
+            options
+                .iter()
+                .position(|opt| opt.eq_ignore_ascii_case(line))
+                .ok_or_else(|| format!("Please type a number from 1 to {}", count))
+        })
+    }
+
+    fn choose(&mut self, step: &Step) -> Result<String> {
+        let mut history = step.history.then(|| History::load(&step.id));
+        let picked = self.pick(
+            &step.prompt,
+            &step.options,
+            step.default.as_deref(),
+            history.as_ref(),
+        )?;
+        let choice = step.options[picked].clone();
+        self.remember(history.as_mut(), &choice);
+        Ok(choice)
+    }
+
+    fn input(&mut self, step: &Step) -> Result<String> {
+        let shown = text::isolate(&text::sanitize(&step.prompt));
+        let prompt = match &step.default {
+            Some(default) => format!("{} [{}]: ", shown, text::sanitize(default)),
+            None => format!("{}: ", shown),
+        };
+        let mut history = step.history.then(|| History::load(&step.id));
+        let text = self.ask_until(&prompt, history.as_ref(), |line| {
+            match (&step.default, line.is_empty()) {
+                (Some(default), true) => Ok(default.clone()),
+                (None, true) if step.required => Err("An answer is required".to_string()),
+                _ => Ok(line.to_string()),
+            }
+        })?;
+        self.remember(history.as_mut(), &text);
+        Ok(text)
+    }
+}
+
+/// The answer to a choose or input step when not asking.
+fn unasked(step: &Step) -> Result<String> {
+    match (&step.default, step.kind) {
+        (Some(default), _) => Ok(default.clone()),
+        (None, StepKind::Input) if !step.required => Ok(String::new()),
+        (None, _) => bail!("Not asking, and step {:?} has no default", step.id),
+    }
+}
+
+pub fn run(opts: &MainOptions, wizard: &WizardOptions) -> Result<()> {
+    let definition = Definition::load(&wizard.file)?;
+
+    // stdout is reserved for the results.
+    let mut terminal = opts.terminal()?;
+    if let PromptSink::Stdout = terminal.sink {
+        terminal.sink = PromptSink::Stderr;
+    }
+    terminal.events = None;
+    let mut asker = Asker { opts, terminal };
+
+    let mut answers = Answers::default();
+    for step in &definition.steps {
+        if let Some(when) = &step.when {
+            if !holds(when, &answers) {
+                continue;
+            }
+        }
+        let value = match step.kind {
+            StepKind::Confirm if opts.preset().is_some() => {
+                Value::Bool(opts.preset() == Some(Answer::Yes))
+            }
+            _ if opts.preset().is_some() => Value::Text(unasked(step)?),
+            StepKind::Confirm => {
+                let mut step_opts = opts.clone();
+                step_opts.prompt = step.prompt.clone();
+                if let Some(default) = &step.default {
+                    step_opts.default = parse_confirm_default(default)?;
+                }
+                let mut confirm = step_opts.confirm_on(asker.terminal);
+                let decision = confirm.ask_loop();
+                asker.terminal = confirm.into_terminal();
+                // The reason has been shown already, and later steps may
+                // depend on this one, so there's no going on without it.
+                match decision {
+                    Decision::Yes => Value::Bool(true),
+                    Decision::No | Decision::Neutral => Value::Bool(false),
+                    Decision::TimedOut(answer) => Value::Bool(answer == Answer::Yes),
+                    _ => bail!("Step {:?} wasn't answered, stopping", step.id),
+                }
+            }
+            StepKind::Choose => Value::Text(asker.choose(step)?),
+            StepKind::Input => Value::Text(asker.input(step)?),
+        };
+        answers.0.push((step.id.clone(), value));
+    }
+
+    let format = match opts.shell_output {
+        true => Format::Shell,
+        false => wizard.output,
+    };
+    match format {
+        Format::Json => println!("{}", serde_json::to_string(&answers)?),
+        Format::Shell => {
+            let vars: Vec<_> = (answers.0.iter())
+                .map(|(id, value)| (shell::answer_var(id), value.as_text()))
+                .collect();
+            println!("{}", shell::report(&vars));
+        }
+    }
+    Ok(())
+}